@@ -1403,7 +1403,7 @@ mod tests {
     };
     use reth_stages_api::StageCheckpoint;
     use reth_trie::{root::state_root_unhashed, StateRoot};
-    use std::collections::HashMap;
+    use std::{collections::HashMap, time::Duration};
 
     fn setup_externals(
         exec_res: Vec<ExecutionOutcome>,
@@ -1881,6 +1881,33 @@ mod tests {
         assert_eq!(state_root, block5.state_root);
     }
 
+    #[test]
+    fn instrumented_read_transactions() {
+        let data = BlockchainTestData::default_from_number(11);
+        let (block1, exec1) = data.blocks[0].clone();
+        let genesis = data.genesis;
+
+        let externals = setup_externals(vec![exec1])
+            .with_instrumented_read_transactions(Duration::from_secs(60));
+        setup_genesis(&externals.provider_factory, genesis);
+
+        let config = BlockchainTreeConfig::new(1, 2, 3, 2);
+        let mut tree = BlockchainTree::new(externals, config).expect("failed to create tree");
+        tree.make_canonical(B256::ZERO).unwrap();
+        tree.finalize_block(10).unwrap();
+
+        // The block is executed and its state root is validated through the instrumented
+        // provider factory
+        assert_eq!(
+            tree.insert_block(block1.clone(), BlockValidationKind::Exhaustive).unwrap(),
+            InsertPayloadOk::Inserted(BlockStatus::Valid(BlockAttachment::Canonical))
+        );
+        assert_eq!(
+            tree.make_canonical(block1.hash()).unwrap(),
+            CanonicalOutcome::Committed { head: block1.header.clone() }
+        );
+    }
+
     #[test]
     fn test_side_chain_fork() {
         let data = BlockchainTestData::default_from_number(11);
@@ -2375,11 +2402,13 @@ mod tests {
             provider_factory: externals.provider_factory.clone(),
             executor_factory: externals.executor_factory.clone(),
             consensus: externals.consensus.clone(),
+            instrumented_provider_factory: externals.instrumented_provider_factory.clone(),
         };
         let cloned_externals_2 = TreeExternals {
             provider_factory: externals.provider_factory.clone(),
             executor_factory: externals.executor_factory.clone(),
             consensus: externals.consensus.clone(),
+            instrumented_provider_factory: externals.instrumented_provider_factory.clone(),
         };
 
         // last finalized block would be number 9.
@@ -18,7 +18,8 @@ use reth_execution_types::{Chain, ExecutionOutcome};
 use reth_primitives::{GotExpected, SealedBlockWithSenders, SealedHeader};
 use reth_provider::{
     providers::{BundleStateProvider, ConsistentDbView, ProviderNodeTypes},
-    FullExecutionDataProvider, ProviderError, StateRootProvider, TryIntoHistoricalStateProvider,
+    FullExecutionDataProvider, ProviderError, ProviderFactory, StateRootProvider,
+    TryIntoHistoricalStateProvider,
 };
 use reth_revm::database::StateProviderDatabase;
 use reth_trie::{updates::TrieUpdates, HashedPostState, TrieInput};
@@ -182,6 +183,42 @@ impl AppendableChain {
         // some checks are done before blocks comes here.
         externals.consensus.validate_header_against_parent(&block, parent_block)?;
 
+        match &externals.instrumented_provider_factory {
+            Some(provider_factory) => Self::execute_and_validate_state(
+                block,
+                bundle_state_data_provider,
+                provider_factory.clone(),
+                externals,
+                block_attachment,
+                block_validation_kind,
+            ),
+            None => Self::execute_and_validate_state(
+                block,
+                bundle_state_data_provider,
+                externals.provider_factory.clone(),
+                externals,
+                block_attachment,
+                block_validation_kind,
+            ),
+        }
+    }
+
+    /// Executes the given block on top of the state read through the given provider factory, and
+    /// validates its state root if requested, see [`Self::validate_and_execute`].
+    fn execute_and_validate_state<EDP, P, N, E>(
+        block: SealedBlockWithSenders,
+        bundle_state_data_provider: EDP,
+        provider_factory: ProviderFactory<P>,
+        externals: &TreeExternals<N, E>,
+        block_attachment: BlockAttachment,
+        block_validation_kind: BlockValidationKind,
+    ) -> Result<(ExecutionOutcome, Option<TrieUpdates>), BlockExecutionError>
+    where
+        EDP: FullExecutionDataProvider,
+        P: ProviderNodeTypes,
+        N: ProviderNodeTypes,
+        E: BlockExecutorProvider,
+    {
         // get the state provider.
         let canonical_fork = bundle_state_data_provider.canonical_fork();
 
@@ -192,8 +229,7 @@ impl AppendableChain {
         // It is safe to use consistent view without any special error handling as long as
         // we guarantee that plain state cannot change during processing of new payload.
         // The usage has to be re-evaluated if that was ever to change.
-        let consistent_view =
-            ConsistentDbView::new_with_latest_tip(externals.provider_factory.clone())?;
+        let consistent_view = ConsistentDbView::new_with_latest_tip(provider_factory)?;
         let state_provider = consistent_view
             .provider_ro()?
             // State root calculation can take a while, and we're sure no write transaction
@@ -4,14 +4,15 @@ use alloy_primitives::{BlockHash, BlockNumber};
 use reth_consensus::Consensus;
 use reth_db::{static_file::HeaderMask, tables};
 use reth_db_api::{cursor::DbCursorRO, transaction::DbTx};
-use reth_node_types::NodeTypesWithDB;
+use reth_node_types::{NodeTypesWithDB, NodeTypesWithDBAdapter};
 use reth_primitives::StaticFileSegment;
 use reth_provider::{
-    providers::ProviderNodeTypes, FinalizedBlockReader, FinalizedBlockWriter, ProviderFactory,
-    StaticFileProviderFactory, StatsReader,
+    providers::{InstrumentedDatabase, ProviderNodeTypes},
+    FinalizedBlockReader, FinalizedBlockWriter, ProviderFactory, StaticFileProviderFactory,
+    StatsReader,
 };
 use reth_storage_errors::provider::ProviderResult;
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 /// A container for external components.
 ///
@@ -30,6 +31,10 @@ pub struct TreeExternals<N: NodeTypesWithDB, E> {
     pub(crate) consensus: Arc<dyn Consensus>,
     /// The executor factory to execute blocks with.
     pub(crate) executor_factory: E,
+    /// The provider factory with instrumented read-only transactions, used instead of
+    /// [`Self::provider_factory`] to execute and validate blocks if set.
+    pub(crate) instrumented_provider_factory:
+        Option<ProviderFactory<NodeTypesWithDBAdapter<N, InstrumentedDatabase<N::DB>>>>,
 }
 
 impl<N: ProviderNodeTypes, E> TreeExternals<N, E> {
@@ -39,7 +44,18 @@ impl<N: ProviderNodeTypes, E> TreeExternals<N, E> {
         consensus: Arc<dyn Consensus>,
         executor_factory: E,
     ) -> Self {
-        Self { provider_factory, consensus, executor_factory }
+        Self { provider_factory, consensus, executor_factory, instrumented_provider_factory: None }
+    }
+
+    /// Instruments the read-only database transactions opened to execute and validate blocks,
+    /// warning about the ones held open for longer than the given threshold.
+    ///
+    /// See [`ProviderFactory::with_instrumented_read_transactions`].
+    pub fn with_instrumented_read_transactions(mut self, long_lived_threshold: Duration) -> Self {
+        self.instrumented_provider_factory = Some(
+            self.provider_factory.clone().with_instrumented_read_transactions(long_lived_threshold),
+        );
+        self
     }
 }
 
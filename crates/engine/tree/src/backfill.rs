@@ -7,10 +7,15 @@
 //!
 //! These modes are mutually exclusive and the node can only be in one mode at a time.
 
-use futures::FutureExt;
+use alloy_primitives::BlockNumber;
+use futures::{FutureExt, StreamExt};
 use reth_provider::providers::ProviderNodeTypes;
-use reth_stages_api::{ControlFlow, Pipeline, PipelineError, PipelineTarget, PipelineWithResult};
+use reth_stages_api::{
+    ControlFlow, Pipeline, PipelineError, PipelineEvent, PipelineTarget, PipelineWithResult,
+    StageId,
+};
 use reth_tasks::TaskSpawner;
+use reth_tokio_util::EventStream;
 use std::task::{ready, Context, Poll};
 use tokio::sync::oneshot;
 use tracing::trace;
@@ -67,6 +72,8 @@ pub enum BackfillAction {
 pub enum BackfillEvent {
     /// Backfill sync started.
     Started(PipelineTarget),
+    /// Backfill sync made progress.
+    Progress(PipelineProgress),
     /// Backfill sync finished.
     ///
     /// If this is returned, backfill sync is idle.
@@ -76,6 +83,15 @@ pub enum BackfillEvent {
     TaskDropped(String),
 }
 
+/// Progress of a running backfill sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineProgress {
+    /// The stage that made progress.
+    pub stage_id: StageId,
+    /// The highest block processed by the stage.
+    pub block_number: BlockNumber,
+}
+
 /// Pipeline sync.
 #[derive(Debug)]
 pub struct PipelineSync<N: ProviderNodeTypes> {
@@ -86,6 +102,8 @@ pub struct PipelineSync<N: ProviderNodeTypes> {
     pipeline_state: PipelineState<N>,
     /// Pending target block for the pipeline to sync
     pending_pipeline_target: Option<PipelineTarget>,
+    /// Events of the running pipeline, used to report progress.
+    pipeline_events: Option<EventStream<PipelineEvent>>,
 }
 
 impl<N: ProviderNodeTypes> PipelineSync<N> {
@@ -95,6 +113,7 @@ impl<N: ProviderNodeTypes> PipelineSync<N> {
             pipeline_task_spawner,
             pipeline_state: PipelineState::Idle(Some(pipeline)),
             pending_pipeline_target: None,
+            pipeline_events: None,
         }
     }
 
@@ -138,6 +157,7 @@ impl<N: ProviderNodeTypes> PipelineSync<N> {
                 let (tx, rx) = oneshot::channel();
 
                 let pipeline = pipeline.take().expect("exists");
+                self.pipeline_events = Some(pipeline.events());
                 self.pipeline_task_spawner.spawn_critical_blocking(
                     "pipeline task",
                     Box::pin(async move {
@@ -163,6 +183,7 @@ impl<N: ProviderNodeTypes> PipelineSync<N> {
                 ready!(fut.poll_unpin(cx))
             }
         };
+        self.pipeline_events = None;
         let ev = match res {
             Ok((pipeline, result)) => {
                 self.pipeline_state = PipelineState::Idle(Some(pipeline));
@@ -175,6 +196,20 @@ impl<N: ProviderNodeTypes> PipelineSync<N> {
         };
         Poll::Ready(ev)
     }
+
+    /// Returns the next progress report of the running pipeline, if any.
+    fn poll_progress(&mut self, cx: &mut Context<'_>) -> Poll<PipelineProgress> {
+        let Some(events) = &mut self.pipeline_events else { return Poll::Pending };
+        while let Poll::Ready(Some(event)) = events.poll_next_unpin(cx) {
+            if let PipelineEvent::Ran { stage_id, result, .. } = event {
+                return Poll::Ready(PipelineProgress {
+                    stage_id,
+                    block_number: result.checkpoint.block_number,
+                })
+            }
+        }
+        Poll::Pending
+    }
 }
 
 impl<N: ProviderNodeTypes> BackfillSync for PipelineSync<N> {
@@ -192,6 +227,11 @@ impl<N: ProviderNodeTypes> BackfillSync for PipelineSync<N> {
 
         // make sure we poll the pipeline if it's active, and return any ready pipeline events
         if self.is_pipeline_active() {
+            // report the progress of the pipeline before its result
+            if let Poll::Ready(progress) = self.poll_progress(cx) {
+                return Poll::Ready(BackfillEvent::Progress(progress))
+            }
+
             // advance the pipeline
             if let Poll::Ready(event) = self.poll_pipeline(cx) {
                 return Poll::Ready(event)
@@ -306,9 +346,15 @@ mod tests {
             assert_eq!(target.sync_target().unwrap(), tip);
         });
 
-        // the next event should be the pipeline finishing in a good state
-        let sync_future = poll_fn(|cx| pipeline_sync.poll(cx));
-        let next_ready = sync_future.await;
+        // the pipeline reports progress and then finishes in a good state
+        let mut progress = Vec::new();
+        let next_ready = loop {
+            match poll_fn(|cx| pipeline_sync.poll(cx)).await {
+                BackfillEvent::Progress(p) => progress.push(p),
+                event => break event,
+            }
+        };
+        assert!(!progress.is_empty());
         assert_matches!(next_ready, BackfillEvent::Finished(result) => {
             assert_matches!(result, Ok(control_flow) => assert_eq!(control_flow, ControlFlow::Continue { block_number: PIPELINE_DONE_AFTER }));
         });
@@ -1,11 +1,16 @@
-use crate::backfill::{BackfillAction, BackfillEvent, BackfillSync};
-use futures::Stream;
+use crate::backfill::{BackfillAction, BackfillEvent, BackfillSync, PipelineProgress};
+use alloy_primitives::BlockNumber;
+use futures::{stream::FusedStream, Stream};
 use reth_stages_api::{ControlFlow, PipelineTarget};
 use std::{
+    collections::VecDeque,
     fmt::{Display, Formatter, Result},
+    future::{poll_fn, Future},
     pin::Pin,
-    task::{Context, Poll},
+    task::{ready, Context, Poll},
+    time::Duration,
 };
+use tokio::time::{Instant, Sleep};
 use tracing::*;
 
 /// The type that drives the chain forward.
@@ -27,7 +32,8 @@ use tracing::*;
 ///
 /// The [`ChainOrchestrator`] polls the [`ChainHandler`] to advance the chain and handles the
 /// emitted events. Requests and events are passed to the [`ChainHandler`] via
-/// [`ChainHandler::on_event`].
+/// [`ChainHandler::on_event`], once [`ChainHandler::poll_ready`] reports that the handler is ready
+/// to accept them.
 #[must_use = "Stream does nothing unless polled"]
 #[derive(Debug)]
 pub struct ChainOrchestrator<T, P>
@@ -39,6 +45,16 @@ where
     handler: T,
     /// Controls backfill sync.
     backfill_sync: P,
+    /// Detects a stalled handler, if enabled.
+    handler_watchdog: Option<HandlerWatchdog>,
+    /// Whether the backfill sync is currently running.
+    is_backfill_active: bool,
+    /// Whether a [`ChainEvent::FatalError`] was emitted, after which the stream is terminated.
+    is_terminated: bool,
+    /// Events held back while the handler is busy, see [`ChainHandler::poll_ready`].
+    deferred_events: VecDeque<FromOrchestrator>,
+    /// Records the recent events for debugging, if enabled.
+    event_log: Option<EventLog>,
 }
 
 impl<T, P> ChainOrchestrator<T, P>
@@ -48,7 +64,45 @@ where
 {
     /// Creates a new [`ChainOrchestrator`] with the given handler and backfill sync.
     pub const fn new(handler: T, backfill_sync: P) -> Self {
-        Self { handler, backfill_sync }
+        Self {
+            handler,
+            backfill_sync,
+            handler_watchdog: None,
+            is_backfill_active: false,
+            is_terminated: false,
+            deferred_events: VecDeque::new(),
+            event_log: None,
+        }
+    }
+
+    /// Enables the handler watchdog with the given threshold.
+    ///
+    /// If the handler doesn't produce any event within the threshold while the backfill sync is
+    /// not running, a warning is logged and [`ChainEvent::HandlerStalled`] is emitted. This is
+    /// repeated every threshold until the handler produces an event.
+    ///
+    /// The watchdog is driven by a timer polled alongside the handler, so it never blocks.
+    pub fn with_handler_stall_threshold(mut self, threshold: Duration) -> Self {
+        self.handler_watchdog = Some(HandlerWatchdog::new(threshold));
+        self
+    }
+
+    /// Enables the event log that keeps the given number of the most recent events.
+    ///
+    /// Events passed to the handler, emitted by the handler and yielded by the orchestrator are
+    /// recorded with timestamps, see [`ChainOrchestrator::recent_events`]. Once the log is full,
+    /// the oldest event is dropped for each new one.
+    pub fn with_event_log(mut self, capacity: usize) -> Self {
+        self.event_log = Some(EventLog::new(capacity));
+        self
+    }
+
+    /// Returns the recorded events from the oldest to the newest, or an empty iterator if the
+    /// event log is disabled.
+    ///
+    /// See [`ChainOrchestrator::with_event_log`].
+    pub fn recent_events(&self) -> impl Iterator<Item = &EventLogEntry> + '_ {
+        self.event_log.iter().flat_map(|log| log.entries.iter())
     }
 
     /// Returns the handler
@@ -61,6 +115,96 @@ where
         &mut self.handler
     }
 
+    /// Returns a snapshot of the orchestrator's current state, including the handler's view.
+    pub fn state(&self) -> OrchestratorSnapshot<T::Snapshot> {
+        OrchestratorSnapshot {
+            is_backfill_active: self.is_backfill_active,
+            handler: self.handler.snapshot(),
+        }
+    }
+
+    /// Gracefully shuts down the orchestrator.
+    ///
+    /// Waits for the in-flight backfill sync to finish, if any, and then notifies the handler
+    /// with [`FromOrchestrator::Shutdown`], so that it can complete its pending writes. The
+    /// returned future resolves once the handler completed them, see
+    /// [`ChainHandler::poll_shutdown`], and it's safe to drop the orchestrator.
+    ///
+    /// Events held back while the handler is busy are delivered before the shutdown
+    /// notification, so the future also waits for the handler to become ready.
+    pub fn shutdown(&mut self) -> impl Future<Output = ()> + '_ {
+        let mut is_handler_notified = false;
+        poll_fn(move |cx| {
+            if is_handler_notified {
+                return self.handler.poll_shutdown(cx)
+            }
+
+            while self.is_backfill_active {
+                debug!(target: "engine::tree", "waiting for backfill sync to finish before shutdown");
+                match ready!(self.backfill_sync.poll(cx)) {
+                    BackfillEvent::Started(_) | BackfillEvent::Progress(_) => {}
+                    BackfillEvent::Finished(res) => {
+                        self.is_backfill_active = false;
+                        match res {
+                            Ok(ctrl) => self
+                                .notify_handler(cx, FromOrchestrator::BackfillSyncFinished(ctrl)),
+                            Err(err) => {
+                                error!(target: "engine::tree", %err, "backfill sync failed")
+                            }
+                        }
+                    }
+                    BackfillEvent::TaskDropped(err) => {
+                        self.is_backfill_active = false;
+                        error!(target: "engine::tree", %err, "backfill sync task dropped");
+                    }
+                }
+            }
+
+            ready!(self.poll_deferred_events(cx));
+            ready!(self.handler.poll_ready(cx));
+            self.deliver_event(FromOrchestrator::Shutdown);
+            is_handler_notified = true;
+            self.handler.poll_shutdown(cx)
+        })
+    }
+
+    /// Passes the event to the handler if it's ready, otherwise holds it back until the handler
+    /// is ready again.
+    fn notify_handler(&mut self, cx: &mut Context<'_>, event: FromOrchestrator) {
+        if self.deferred_events.is_empty() && self.handler.poll_ready(cx).is_ready() {
+            self.deliver_event(event);
+        } else {
+            trace!(target: "engine::tree", ?event, "handler is busy, deferring event");
+            self.deferred_events.push_back(event);
+        }
+    }
+
+    /// Passes the held back events to the handler in order, for as long as the handler is ready.
+    ///
+    /// Returns [`Poll::Ready`] once all held back events were delivered.
+    fn poll_deferred_events(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        while !self.deferred_events.is_empty() {
+            ready!(self.handler.poll_ready(cx));
+            if let Some(event) = self.deferred_events.pop_front() {
+                self.deliver_event(event);
+            }
+        }
+        Poll::Ready(())
+    }
+
+    /// Passes the event to the handler and records it in the event log.
+    fn deliver_event(&mut self, event: FromOrchestrator) {
+        self.record_event(|| LoggedEvent::FromOrchestrator(event.clone()));
+        self.handler.on_event(event);
+    }
+
+    /// Records the event in the event log, if enabled.
+    fn record_event(&mut self, event: impl FnOnce() -> LoggedEvent) {
+        if let Some(log) = &mut self.event_log {
+            log.push(event());
+        }
+    }
+
     /// Triggers a backfill sync for the __valid__ given target.
     ///
     /// CAUTION: This function should be used with care and with a valid target.
@@ -75,6 +219,9 @@ where
     fn poll_next_event(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<ChainEvent<T::Event>> {
         let this = self.get_mut();
 
+        // deliver the events held back while the handler was busy, if it's ready again
+        let _ = this.poll_deferred_events(cx);
+
         // This loop polls the components
         //
         // 1. Polls the backfill sync to completion, if active.
@@ -84,27 +231,42 @@ where
             match this.backfill_sync.poll(cx) {
                 Poll::Ready(backfill_sync_event) => match backfill_sync_event {
                     BackfillEvent::Started(_) => {
+                        this.is_backfill_active = true;
                         // notify handler that backfill sync started
-                        this.handler.on_event(FromOrchestrator::BackfillSyncStarted);
+                        this.notify_handler(cx, FromOrchestrator::BackfillSyncStarted);
                         return Poll::Ready(ChainEvent::BackfillSyncStarted);
                     }
+                    BackfillEvent::Progress(progress) => {
+                        return Poll::Ready(ChainEvent::BackfillSyncProgress(progress));
+                    }
                     BackfillEvent::Finished(res) => {
+                        this.is_backfill_active = false;
+                        // the handler can only make progress after the backfill sync finished
+                        if let Some(watchdog) = &mut this.handler_watchdog {
+                            watchdog.reset();
+                        }
                         return match res {
                             Ok(ctrl) => {
                                 tracing::debug!(?ctrl, "backfill sync finished");
                                 // notify handler that backfill sync finished
-                                this.handler.on_event(FromOrchestrator::BackfillSyncFinished(ctrl));
+                                this.notify_handler(
+                                    cx,
+                                    FromOrchestrator::BackfillSyncFinished(ctrl),
+                                );
                                 Poll::Ready(ChainEvent::BackfillSyncFinished)
                             }
                             Err(err) => {
                                 tracing::error!( %err, "backfill sync failed");
-                                Poll::Ready(ChainEvent::FatalError)
+                                Poll::Ready(ChainEvent::FatalError(err.into()))
                             }
                         }
                     }
                     BackfillEvent::TaskDropped(err) => {
+                        this.is_backfill_active = false;
                         tracing::error!( %err, "backfill sync task dropped");
-                        return Poll::Ready(ChainEvent::FatalError);
+                        return Poll::Ready(ChainEvent::FatalError(
+                            format!("backfill sync task dropped: {err}").into(),
+                        ));
                     }
                 },
                 Poll::Pending => {}
@@ -113,6 +275,11 @@ where
             // poll the handler for the next event
             match this.handler.poll(cx) {
                 Poll::Ready(handler_event) => {
+                    if let Some(watchdog) = &mut this.handler_watchdog {
+                        watchdog.reset();
+                    }
+                    this.record_event(|| LoggedEvent::Handler((&handler_event).into()));
+
                     match handler_event {
                         HandlerEvent::BackfillAction(action) => {
                             // forward action to backfill_sync
@@ -123,9 +290,9 @@ where
                             // bubble up the event
                             return Poll::Ready(ChainEvent::Handler(ev));
                         }
-                        HandlerEvent::FatalError => {
-                            error!(target: "engine::tree", "Fatal error");
-                            return Poll::Ready(ChainEvent::FatalError)
+                        HandlerEvent::FatalError(err) => {
+                            error!(target: "engine::tree", %err, "Fatal error");
+                            return Poll::Ready(ChainEvent::FatalError(err))
                         }
                     }
                 }
@@ -136,10 +303,159 @@ where
             }
         }
 
+        // check if the handler stalled, the backfill sync is allowed to take as long as it needs
+        if let Some(watchdog) = &mut this.handler_watchdog {
+            if this.is_backfill_active {
+                watchdog.reset();
+            } else if let Poll::Ready(stalled_for) = watchdog.poll_stalled(cx) {
+                warn!(
+                    target: "engine::tree",
+                    ?stalled_for,
+                    threshold = ?watchdog.threshold,
+                    "Chain handler hasn't produced any events within the threshold"
+                );
+                return Poll::Ready(ChainEvent::HandlerStalled)
+            }
+        }
+
         Poll::Pending
     }
 }
 
+/// A bounded log of the recent events of the [`ChainOrchestrator`].
+#[derive(Debug)]
+struct EventLog {
+    /// The maximum number of entries.
+    capacity: usize,
+    /// The entries from the oldest to the newest.
+    entries: VecDeque<EventLogEntry>,
+}
+
+impl EventLog {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, entries: VecDeque::with_capacity(capacity) }
+    }
+
+    /// Records the event, dropping the oldest entry if the log is full.
+    fn push(&mut self, event: LoggedEvent) {
+        if self.capacity == 0 {
+            return
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(EventLogEntry { recorded_at: Instant::now(), event });
+    }
+}
+
+/// An entry of the [`ChainOrchestrator`] event log, see [`ChainOrchestrator::recent_events`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventLogEntry {
+    /// The time the event was recorded at.
+    pub recorded_at: Instant,
+    /// The recorded event.
+    pub event: LoggedEvent,
+}
+
+/// An event recorded in the [`ChainOrchestrator`] event log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoggedEvent {
+    /// An event passed to the handler.
+    FromOrchestrator(FromOrchestrator),
+    /// An event emitted by the handler.
+    Handler(LoggedHandlerEvent),
+    /// An event yielded by the orchestrator.
+    Chain(LoggedChainEvent),
+}
+
+/// A recorded [`HandlerEvent`], without the handler specific payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoggedHandlerEvent {
+    /// [`HandlerEvent::BackfillAction`]
+    BackfillAction(BackfillAction),
+    /// [`HandlerEvent::Event`]
+    Event,
+    /// [`HandlerEvent::FatalError`] with the error message.
+    FatalError(String),
+}
+
+impl<T> From<&HandlerEvent<T>> for LoggedHandlerEvent {
+    fn from(event: &HandlerEvent<T>) -> Self {
+        match event {
+            HandlerEvent::BackfillAction(action) => Self::BackfillAction(action.clone()),
+            HandlerEvent::Event(_) => Self::Event,
+            HandlerEvent::FatalError(err) => Self::FatalError(err.to_string()),
+        }
+    }
+}
+
+/// A recorded [`ChainEvent`], without the handler specific payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoggedChainEvent {
+    /// [`ChainEvent::BackfillSyncStarted`]
+    BackfillSyncStarted,
+    /// [`ChainEvent::BackfillSyncProgress`]
+    BackfillSyncProgress(PipelineProgress),
+    /// [`ChainEvent::BackfillSyncFinished`]
+    BackfillSyncFinished,
+    /// [`ChainEvent::FatalError`] with the error message.
+    FatalError(String),
+    /// [`ChainEvent::HandlerStalled`]
+    HandlerStalled,
+    /// [`ChainEvent::Handler`]
+    Handler,
+}
+
+impl<T> From<&ChainEvent<T>> for LoggedChainEvent {
+    fn from(event: &ChainEvent<T>) -> Self {
+        match event {
+            ChainEvent::BackfillSyncStarted => Self::BackfillSyncStarted,
+            ChainEvent::BackfillSyncProgress(progress) => Self::BackfillSyncProgress(*progress),
+            ChainEvent::BackfillSyncFinished => Self::BackfillSyncFinished,
+            ChainEvent::FatalError(err) => Self::FatalError(err.to_string()),
+            ChainEvent::HandlerStalled => Self::HandlerStalled,
+            ChainEvent::Handler(_) => Self::Handler,
+        }
+    }
+}
+
+/// Detects a [`ChainHandler`] that hasn't produced any events within a threshold.
+#[derive(Debug)]
+struct HandlerWatchdog {
+    /// The maximum duration between two handler events.
+    threshold: Duration,
+    /// The time of the last handler event.
+    last_event_at: Instant,
+    /// Fires when the threshold is reached. Created lazily on the first poll, so that the
+    /// watchdog can be configured outside of the runtime context.
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl HandlerWatchdog {
+    fn new(threshold: Duration) -> Self {
+        Self { threshold, last_event_at: Instant::now(), deadline: None }
+    }
+
+    /// Resets the watchdog after the handler made progress.
+    fn reset(&mut self) {
+        self.last_event_at = Instant::now();
+        if let Some(deadline) = &mut self.deadline {
+            deadline.as_mut().reset(self.last_event_at + self.threshold);
+        }
+    }
+
+    /// Returns the time since the last handler event, if the threshold was reached. The watchdog
+    /// is then re-armed to fire again after another threshold.
+    fn poll_stalled(&mut self, cx: &mut Context<'_>) -> Poll<Duration> {
+        let deadline = self.deadline.get_or_insert_with(|| {
+            Box::pin(tokio::time::sleep_until(self.last_event_at + self.threshold))
+        });
+        ready!(deadline.as_mut().poll(cx));
+        deadline.as_mut().reset(Instant::now() + self.threshold);
+        Poll::Ready(self.last_event_at.elapsed())
+    }
+}
+
 impl<T, P> Stream for ChainOrchestrator<T, P>
 where
     T: ChainHandler + Unpin,
@@ -147,11 +463,35 @@ where
 {
     type Item = ChainEvent<T::Event>;
 
+    /// Yields the events of the orchestrator. The stream terminates after yielding a
+    /// [`ChainEvent::FatalError`].
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.as_mut().poll_next_event(cx).map(Some)
+        if self.is_terminated {
+            return Poll::Ready(None)
+        }
+
+        let event = ready!(self.as_mut().poll_next_event(cx));
+        self.record_event(|| LoggedEvent::Chain((&event).into()));
+        if matches!(event, ChainEvent::FatalError(_)) {
+            self.is_terminated = true;
+        }
+        Poll::Ready(Some(event))
+    }
+}
+
+impl<T, P> FusedStream for ChainOrchestrator<T, P>
+where
+    T: ChainHandler + Unpin,
+    P: BackfillSync + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.is_terminated
     }
 }
 
+/// An unrecoverable error of the [`ChainOrchestrator`] or its [`ChainHandler`].
+pub type FatalError = Box<dyn std::error::Error + Send + Sync>;
+
 /// Event emitted by the [`ChainOrchestrator`]
 ///
 /// These are meant to be used for observability and debugging purposes.
@@ -159,10 +499,17 @@ where
 pub enum ChainEvent<T> {
     /// Backfill sync started
     BackfillSyncStarted,
+    /// Backfill sync made progress
+    BackfillSyncProgress(PipelineProgress),
     /// Backfill sync finished
     BackfillSyncFinished,
-    /// Fatal error
-    FatalError,
+    /// Unrecoverable error, after which the chain can't advance anymore.
+    ///
+    /// Emitted if the backfill sync failed or its task was dropped, or if the handler emitted
+    /// [`HandlerEvent::FatalError`]. This is the last event of the [`ChainOrchestrator`] stream.
+    FatalError(FatalError),
+    /// The handler hasn't produced any events within the configured threshold
+    HandlerStalled,
     /// Event emitted by the handler
     Handler(T),
 }
@@ -173,11 +520,17 @@ impl<T: Display> Display for ChainEvent<T> {
             Self::BackfillSyncStarted => {
                 write!(f, "BackfillSyncStarted")
             }
+            Self::BackfillSyncProgress(progress) => {
+                write!(f, "BackfillSyncProgress({}, {})", progress.stage_id, progress.block_number)
+            }
             Self::BackfillSyncFinished => {
                 write!(f, "BackfillSyncFinished")
             }
-            Self::FatalError => {
-                write!(f, "FatalError")
+            Self::FatalError(err) => {
+                write!(f, "FatalError({err})")
+            }
+            Self::HandlerStalled => {
+                write!(f, "HandlerStalled")
             }
             Self::Handler(event) => {
                 write!(f, "Handler({event})")
@@ -197,33 +550,755 @@ impl<T: Display> Display for ChainEvent<T> {
 /// [`HandlerEvent::BackfillAction`] to start a backfill sync. Events from the [`ChainOrchestrator`]
 /// are passed to the handler via [`ChainHandler::on_event`], e.g.
 /// [`FromOrchestrator::BackfillSyncStarted`] once the backfill sync started or finished.
+///
+/// ## Backpressure
+///
+/// A handler that is overwhelmed can report that it's busy via [`ChainHandler::poll_ready`]. The
+/// [`ChainOrchestrator`] then holds back the events for the handler, and passes them in order once
+/// the handler is ready again. The handler is still polled while it's busy, so that it can work
+/// off its backlog, and the backfill sync keeps running: [`ChainOrchestrator::state`] reflects
+/// the actual backfill sync state, which the handler learns about with a delay.
 pub trait ChainHandler: Send + Sync {
     /// Event generated by this handler that orchestrator can bubble up;
     type Event: Send;
 
+    /// Handler specific status, returned by [`ChainHandler::snapshot`].
+    type Snapshot;
+
     /// Informs the handler about an event from the [`ChainOrchestrator`].
     fn on_event(&mut self, event: FromOrchestrator);
 
+    /// Returns [`Poll::Ready`] if the handler is ready to accept events via
+    /// [`ChainHandler::on_event`].
+    ///
+    /// If the handler is busy, it must arrange for the current task to be woken once it's ready
+    /// again. Handlers are always ready by default.
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
+    /// Returns [`Poll::Ready`] once the handler completed its pending writes after receiving
+    /// [`FromOrchestrator::Shutdown`].
+    ///
+    /// If the handler completes them asynchronously, it must arrange for the current task to be
+    /// woken once they're completed. Handlers complete them in [`ChainHandler::on_event`] by
+    /// default.
+    fn poll_shutdown(&mut self, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
     /// Polls for actions that [`ChainOrchestrator`] should handle.
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>>;
+
+    /// Returns a snapshot of the handler's current status.
+    fn snapshot(&self) -> Self::Snapshot;
+}
+
+/// A [`ChainHandler`] that runs two handlers side by side.
+///
+/// Events from the [`ChainOrchestrator`] are passed to both handlers. The handlers are polled
+/// fairly: after one of them produced an event, the other one is polled first on the next poll.
+///
+/// Events requested by either handler are bubbled up as is, which means:
+///  - If both handlers request a backfill sync, both requests are forwarded to the backfill sync in
+///    the order they were polled. Until the backfill sync starts, the last requested target wins.
+///  - A [`HandlerEvent::FatalError`] of either handler is fatal for both.
+#[derive(Debug)]
+pub struct CombinedHandler<A, B> {
+    /// The first handler.
+    first: A,
+    /// The second handler.
+    second: B,
+    /// Whether the second handler should be polled first on the next poll.
+    poll_second_first: bool,
+}
+
+impl<A, B> CombinedHandler<A, B> {
+    /// Creates a new [`CombinedHandler`] with the given handlers.
+    pub const fn new(first: A, second: B) -> Self {
+        Self { first, second, poll_second_first: false }
+    }
+
+    /// Returns the first handler.
+    pub const fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Returns a mutable reference to the first handler.
+    pub fn first_mut(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    /// Returns the second handler.
+    pub const fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// Returns a mutable reference to the second handler.
+    pub fn second_mut(&mut self) -> &mut B {
+        &mut self.second
+    }
+}
+
+impl<A, B> ChainHandler for CombinedHandler<A, B>
+where
+    A: ChainHandler,
+    B: ChainHandler,
+{
+    type Event = CombinedEvent<A::Event, B::Event>;
+    type Snapshot = (A::Snapshot, B::Snapshot);
+
+    fn on_event(&mut self, event: FromOrchestrator) {
+        self.first.on_event(event.clone());
+        self.second.on_event(event);
+    }
+
+    /// Ready once both handlers are ready, as the events are passed to both.
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let first = self.first.poll_ready(cx);
+        let second = self.second.poll_ready(cx);
+        ready!(first);
+        second
+    }
+
+    /// Ready once both handlers completed their shutdown.
+    fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let first = self.first.poll_shutdown(cx);
+        let second = self.second.poll_shutdown(cx);
+        ready!(first);
+        second
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>> {
+        for _ in 0..2 {
+            let poll_second = self.poll_second_first;
+            // the other handler goes first next time
+            self.poll_second_first = !poll_second;
+
+            let event = if poll_second {
+                self.second.poll(cx).map(|event| event.map_event(CombinedEvent::Second))
+            } else {
+                self.first.poll(cx).map(|event| event.map_event(CombinedEvent::First))
+            };
+            if event.is_ready() {
+                return event
+            }
+        }
+
+        Poll::Pending
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.first.snapshot(), self.second.snapshot())
+    }
+}
+
+/// Event emitted by one of the handlers of a [`CombinedHandler`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CombinedEvent<A, B> {
+    /// Event emitted by the first handler
+    First(A),
+    /// Event emitted by the second handler
+    Second(B),
+}
+
+impl<A: Display, B: Display> Display for CombinedEvent<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::First(event) => write!(f, "First({event})"),
+            Self::Second(event) => write!(f, "Second({event})"),
+        }
+    }
+}
+
+/// A [`ChainHandler`] that imports blocks by running the backfill sync to a fixed target, without
+/// any consensus, e.g. for offline block import.
+///
+/// On the first poll, the handler requests a backfill sync to the target. Once the backfill sync
+/// finished, it emits [`ImportEvent::Synced`] with the block reached by the pipeline, and doesn't
+/// produce any more events. If the pipeline unwound or made no progress without reaching any
+/// block, the import fails with [`HandlerEvent::FatalError`].
+#[derive(Debug)]
+pub struct ImportHandler {
+    /// The target of the import.
+    target: PipelineTarget,
+    /// Whether the backfill sync to the target was requested.
+    is_requested: bool,
+    /// The outcome of the backfill sync, not emitted yet.
+    outcome: Option<ControlFlow>,
+    /// The block the import synced to.
+    synced_block: Option<BlockNumber>,
+}
+
+impl ImportHandler {
+    /// Creates a new [`ImportHandler`] that imports the blocks up to the given target.
+    pub fn new(target: impl Into<PipelineTarget>) -> Self {
+        Self { target: target.into(), is_requested: false, outcome: None, synced_block: None }
+    }
+
+    /// Returns the block the import synced to, or `None` if it's not finished yet.
+    pub const fn synced_block(&self) -> Option<BlockNumber> {
+        self.synced_block
+    }
+}
+
+impl ChainHandler for ImportHandler {
+    type Event = ImportEvent;
+    type Snapshot = Option<BlockNumber>;
+
+    fn on_event(&mut self, event: FromOrchestrator) {
+        if let FromOrchestrator::BackfillSyncFinished(ctrl) = event {
+            self.outcome = Some(ctrl);
+        }
+    }
+
+    fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>> {
+        if !self.is_requested {
+            self.is_requested = true;
+            return Poll::Ready(HandlerEvent::BackfillAction(BackfillAction::Start(self.target)))
+        }
+
+        let Some(ctrl) = self.outcome.take() else { return Poll::Pending };
+        match ctrl.block_number() {
+            Some(block_number) => {
+                debug!(target: "engine::tree", block_number, "block import finished");
+                self.synced_block = Some(block_number);
+                Poll::Ready(HandlerEvent::Event(ImportEvent::Synced { block_number }))
+            }
+            None => Poll::Ready(HandlerEvent::FatalError(
+                format!("block import didn't reach any block: {ctrl:?}").into(),
+            )),
+        }
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.synced_block
+    }
+}
+
+/// Event emitted by the [`ImportHandler`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportEvent {
+    /// The import finished, and the pipeline reached the given block.
+    Synced {
+        /// The block reached by the pipeline.
+        block_number: BlockNumber,
+    },
+}
+
+impl Display for ImportEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Synced { block_number } => write!(f, "Synced({block_number})"),
+        }
+    }
+}
+
+/// A snapshot of the [`ChainOrchestrator`]'s state, see [`ChainOrchestrator::state`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrchestratorSnapshot<S> {
+    /// Whether the backfill sync is currently running.
+    pub is_backfill_active: bool,
+    /// The handler's status.
+    pub handler: S,
 }
 
 /// Events/Requests that the [`ChainHandler`] can emit to the [`ChainOrchestrator`].
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub enum HandlerEvent<T> {
     /// Request an action to backfill sync
     BackfillAction(BackfillAction),
     /// Other event emitted by the handler
     Event(T),
-    /// Fatal error
-    FatalError,
+    /// Unrecoverable error of the handler, terminates the [`ChainOrchestrator`].
+    ///
+    /// Errors the handler can recover from should be handled by the handler itself, or emitted
+    /// as regular events.
+    FatalError(FatalError),
+}
+
+impl<T> HandlerEvent<T> {
+    /// Maps the [`HandlerEvent::Event`] with the given closure.
+    pub fn map_event<U>(self, f: impl FnOnce(T) -> U) -> HandlerEvent<U> {
+        match self {
+            Self::BackfillAction(action) => HandlerEvent::BackfillAction(action),
+            Self::Event(event) => HandlerEvent::Event(f(event)),
+            Self::FatalError(err) => HandlerEvent::FatalError(err),
+        }
+    }
 }
 
 /// Internal events issued by the [`ChainOrchestrator`].
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FromOrchestrator {
     /// Invoked when backfill sync finished
     BackfillSyncFinished(ControlFlow),
     /// Invoked when backfill sync started
     BackfillSyncStarted,
+    /// Invoked when the orchestrator is shutting down.
+    ///
+    /// The handler should complete its pending writes.
+    Shutdown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::IdleBackfillSync;
+    use alloy_primitives::B256;
+    use assert_matches::assert_matches;
+    use futures::StreamExt;
+    use reth_stages_api::StageId;
+    use std::collections::VecDeque;
+
+    /// A handler that records the received events and never produces any events. It reports
+    /// being busy for the given number of readiness polls.
+    #[derive(Debug, Default)]
+    struct StalledHandler {
+        events: Vec<FromOrchestrator>,
+        busy_polls: usize,
+    }
+
+    impl ChainHandler for StalledHandler {
+        type Event = ();
+        type Snapshot = usize;
+
+        fn on_event(&mut self, event: FromOrchestrator) {
+            self.events.push(event);
+        }
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+            if self.busy_polls == 0 {
+                return Poll::Ready(())
+            }
+
+            self.busy_polls -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+
+        fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>> {
+            Poll::Pending
+        }
+
+        fn snapshot(&self) -> Self::Snapshot {
+            self.events.len()
+        }
+    }
+
+    /// A handler that produces the queued events and records the received events.
+    #[derive(Debug, Default)]
+    struct QueueHandler {
+        queue: VecDeque<HandlerEvent<u64>>,
+        events: Vec<FromOrchestrator>,
+    }
+
+    impl ChainHandler for QueueHandler {
+        type Event = u64;
+        type Snapshot = ();
+
+        fn on_event(&mut self, event: FromOrchestrator) {
+            self.events.push(event);
+        }
+
+        fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>> {
+            self.queue.pop_front().map_or(Poll::Pending, Poll::Ready)
+        }
+
+        fn snapshot(&self) -> Self::Snapshot {}
+    }
+
+    /// A backfill sync that records the requested actions and is never started.
+    #[derive(Debug, Default)]
+    struct RecordingBackfillSync {
+        actions: Vec<BackfillAction>,
+    }
+
+    impl BackfillSync for RecordingBackfillSync {
+        fn on_action(&mut self, action: BackfillAction) {
+            self.actions.push(action);
+        }
+
+        fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<BackfillEvent> {
+            Poll::Pending
+        }
+    }
+
+    /// A backfill sync that runs for the given number of polls once started.
+    #[derive(Debug, Default)]
+    struct TestBackfillSync {
+        target: Option<PipelineTarget>,
+        remaining_polls: Option<usize>,
+        polls: usize,
+    }
+
+    impl BackfillSync for TestBackfillSync {
+        fn on_action(&mut self, action: BackfillAction) {
+            let BackfillAction::Start(target) = action;
+            self.target = Some(target);
+        }
+
+        fn poll(&mut self, cx: &mut Context<'_>) -> Poll<BackfillEvent> {
+            if let Some(target) = self.target.take() {
+                return Poll::Ready(BackfillEvent::Started(target))
+            }
+
+            match &mut self.remaining_polls {
+                Some(0) => {
+                    self.remaining_polls = None;
+                    Poll::Ready(BackfillEvent::Finished(Ok(ControlFlow::Continue {
+                        block_number: 10,
+                    })))
+                }
+                Some(remaining_polls) => {
+                    *remaining_polls -= 1;
+                    self.polls += 1;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    /// A backfill sync that reports the given progress once started.
+    #[derive(Debug, Default)]
+    struct ProgressBackfillSync {
+        target: Option<PipelineTarget>,
+        progress: VecDeque<PipelineProgress>,
+    }
+
+    impl BackfillSync for ProgressBackfillSync {
+        fn on_action(&mut self, action: BackfillAction) {
+            let BackfillAction::Start(target) = action;
+            self.target = Some(target);
+        }
+
+        fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<BackfillEvent> {
+            if let Some(target) = self.target.take() {
+                return Poll::Ready(BackfillEvent::Started(target))
+            }
+
+            match self.progress.pop_front() {
+                Some(progress) => Poll::Ready(BackfillEvent::Progress(progress)),
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_stalled() {
+        let threshold = Duration::from_millis(50);
+        let mut orchestrator = ChainOrchestrator::new(StalledHandler::default(), IdleBackfillSync)
+            .with_handler_stall_threshold(threshold);
+
+        let start = Instant::now();
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::HandlerStalled));
+        assert!(start.elapsed() >= threshold);
+
+        // the watchdog fires again after another threshold
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::HandlerStalled));
+        assert!(start.elapsed() >= threshold * 2);
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_backfill_sync() {
+        let backfill_sync = TestBackfillSync { remaining_polls: Some(3), ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(StalledHandler::default(), backfill_sync);
+
+        orchestrator.start_backfill_sync(B256::random());
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+
+        // shutdown waits for the running backfill sync to finish before notifying the handler
+        orchestrator.shutdown().await;
+        assert_eq!(orchestrator.backfill_sync.polls, 3);
+        assert_matches!(
+            orchestrator.handler().events.as_slice(),
+            [
+                FromOrchestrator::BackfillSyncStarted,
+                FromOrchestrator::BackfillSyncFinished(ControlFlow::Continue { block_number: 10 }),
+                FromOrchestrator::Shutdown
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn backfill_sync_progress() {
+        let headers = PipelineProgress { stage_id: StageId::Headers, block_number: 100 };
+        let bodies = PipelineProgress { stage_id: StageId::Bodies, block_number: 50 };
+        let backfill_sync =
+            ProgressBackfillSync { target: None, progress: VecDeque::from([headers, bodies]) };
+        let mut orchestrator = ChainOrchestrator::new(StalledHandler::default(), backfill_sync);
+
+        orchestrator.start_backfill_sync(B256::random());
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+        assert_matches!(
+            orchestrator.next().await,
+            Some(ChainEvent::BackfillSyncProgress(progress)) if progress == headers
+        );
+        assert_matches!(
+            orchestrator.next().await,
+            Some(ChainEvent::BackfillSyncProgress(progress)) if progress == bodies
+        );
+    }
+
+    #[tokio::test]
+    async fn busy_handler_defers_events() {
+        let handler = StalledHandler { busy_polls: 2, ..Default::default() };
+        let backfill_sync = TestBackfillSync { remaining_polls: Some(3), ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(handler, backfill_sync);
+
+        // the backfill sync starts, but the busy handler isn't notified yet
+        orchestrator.start_backfill_sync(B256::random());
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+        assert_eq!(
+            orchestrator.state(),
+            OrchestratorSnapshot { is_backfill_active: true, handler: 0 }
+        );
+
+        // the handler is still busy on the next poll
+        assert!(futures::poll!(orchestrator.next()).is_pending());
+        assert_eq!(orchestrator.handler().busy_polls, 0);
+        assert!(orchestrator.handler().events.is_empty());
+
+        // once ready, the handler receives the held back events in order
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncFinished));
+        assert_matches!(
+            orchestrator.handler().events.as_slice(),
+            [
+                FromOrchestrator::BackfillSyncStarted,
+                FromOrchestrator::BackfillSyncFinished(ControlFlow::Continue { block_number: 10 })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_busy_handler() {
+        let handler = StalledHandler { busy_polls: 3, ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(handler, IdleBackfillSync);
+
+        // shutdown waits for the handler to be ready
+        orchestrator.shutdown().await;
+        assert_eq!(orchestrator.handler().busy_polls, 0);
+        assert_matches!(orchestrator.handler().events.as_slice(), [FromOrchestrator::Shutdown]);
+    }
+
+    #[tokio::test]
+    async fn event_log() {
+        let (first_target, second_target) = (B256::random(), B256::random());
+        let handler = QueueHandler {
+            queue: VecDeque::from([
+                HandlerEvent::BackfillAction(BackfillAction::Start(first_target.into())),
+                HandlerEvent::Event(1),
+                HandlerEvent::BackfillAction(BackfillAction::Start(second_target.into())),
+            ]),
+            ..Default::default()
+        };
+        let backfill_sync = TestBackfillSync { remaining_polls: Some(1), ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(handler, backfill_sync).with_event_log(6);
+
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncFinished));
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::Handler(1)));
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+
+        // the oldest events were dropped to keep the log bounded
+        let events =
+            orchestrator.recent_events().map(|entry| entry.event.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                LoggedEvent::Chain(LoggedChainEvent::BackfillSyncFinished),
+                LoggedEvent::Handler(LoggedHandlerEvent::Event),
+                LoggedEvent::Chain(LoggedChainEvent::Handler),
+                LoggedEvent::Handler(LoggedHandlerEvent::BackfillAction(BackfillAction::Start(
+                    second_target.into()
+                ))),
+                LoggedEvent::FromOrchestrator(FromOrchestrator::BackfillSyncStarted),
+                LoggedEvent::Chain(LoggedChainEvent::BackfillSyncStarted),
+            ]
+        );
+        assert!(orchestrator
+            .recent_events()
+            .zip(orchestrator.recent_events().skip(1))
+            .all(|(older, newer)| older.recorded_at <= newer.recorded_at));
+    }
+
+    #[tokio::test]
+    async fn event_log_disabled() {
+        let handler =
+            QueueHandler { queue: VecDeque::from([HandlerEvent::Event(1)]), ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(handler, IdleBackfillSync);
+
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::Handler(1)));
+        assert_eq!(orchestrator.recent_events().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn import_handler() {
+        let target = B256::random();
+        let backfill_sync = TestBackfillSync { remaining_polls: Some(1), ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(ImportHandler::new(target), backfill_sync);
+
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncFinished));
+        assert_matches!(
+            orchestrator.next().await,
+            Some(ChainEvent::Handler(ImportEvent::Synced { block_number: 10 }))
+        );
+        assert_eq!(orchestrator.handler().synced_block(), Some(10));
+        assert_eq!(
+            orchestrator.state(),
+            OrchestratorSnapshot { is_backfill_active: false, handler: Some(10) }
+        );
+
+        // the import is finished, no more events are produced
+        assert!(futures::poll!(orchestrator.next()).is_pending());
+    }
+
+    #[tokio::test]
+    async fn import_handler_no_progress() {
+        let mut handler = ImportHandler::new(B256::random());
+        handler.is_requested = true;
+        handler.on_event(FromOrchestrator::BackfillSyncFinished(ControlFlow::NoProgress {
+            block_number: None,
+        }));
+        let mut orchestrator = ChainOrchestrator::new(handler, IdleBackfillSync);
+
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::FatalError(_)));
+        assert_matches!(orchestrator.next().await, None);
+        assert_eq!(orchestrator.handler().synced_block(), None);
+    }
+
+    #[tokio::test]
+    async fn shutdown_idle() {
+        let mut orchestrator = ChainOrchestrator::new(StalledHandler::default(), IdleBackfillSync);
+
+        orchestrator.shutdown().await;
+        assert_matches!(orchestrator.handler().events.as_slice(), [FromOrchestrator::Shutdown]);
+    }
+
+    #[tokio::test]
+    async fn state_snapshot() {
+        let backfill_sync = TestBackfillSync { remaining_polls: Some(1), ..Default::default() };
+        let mut orchestrator = ChainOrchestrator::new(StalledHandler::default(), backfill_sync);
+        assert_eq!(
+            orchestrator.state(),
+            OrchestratorSnapshot { is_backfill_active: false, handler: 0 }
+        );
+
+        orchestrator.start_backfill_sync(B256::random());
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncStarted));
+        assert_eq!(
+            orchestrator.state(),
+            OrchestratorSnapshot { is_backfill_active: true, handler: 1 }
+        );
+
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::BackfillSyncFinished));
+        assert_eq!(
+            orchestrator.state(),
+            OrchestratorSnapshot { is_backfill_active: false, handler: 2 }
+        );
+    }
+
+    #[tokio::test]
+    async fn fatal_error_terminates_stream() {
+        let handler = QueueHandler {
+            queue: VecDeque::from([
+                HandlerEvent::Event(1),
+                HandlerEvent::FatalError("handler failed".into()),
+                HandlerEvent::Event(2),
+            ]),
+            ..Default::default()
+        };
+        let mut orchestrator = ChainOrchestrator::new(handler, IdleBackfillSync);
+
+        assert_matches!(orchestrator.next().await, Some(ChainEvent::Handler(1)));
+        assert!(!orchestrator.is_terminated());
+
+        // the error is yielded once, and the stream terminates
+        assert_matches!(
+            orchestrator.next().await,
+            Some(ChainEvent::FatalError(err)) if err.to_string() == "handler failed"
+        );
+        assert!(orchestrator.is_terminated());
+        assert_matches!(orchestrator.next().await, None);
+
+        // the handler isn't polled anymore
+        assert_eq!(orchestrator.handler().queue.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn combined_handler() {
+        let first = QueueHandler {
+            queue: VecDeque::from([HandlerEvent::Event(1), HandlerEvent::Event(2)]),
+            ..Default::default()
+        };
+        let second = QueueHandler {
+            queue: VecDeque::from([
+                HandlerEvent::Event(10),
+                HandlerEvent::Event(20),
+                HandlerEvent::Event(30),
+            ]),
+            ..Default::default()
+        };
+        let mut orchestrator =
+            ChainOrchestrator::new(CombinedHandler::new(first, second), IdleBackfillSync);
+
+        // events from both handlers surface in turns, until one of them runs out of events
+        let mut events = Vec::new();
+        for _ in 0..5 {
+            match orchestrator.next().await {
+                Some(ChainEvent::Handler(event)) => events.push(event),
+                event => panic!("unexpected event: {event:?}"),
+            }
+        }
+        assert_eq!(
+            events,
+            [
+                CombinedEvent::First(1),
+                CombinedEvent::Second(10),
+                CombinedEvent::First(2),
+                CombinedEvent::Second(20),
+                CombinedEvent::Second(30),
+            ]
+        );
+
+        // events from the orchestrator are passed to both handlers
+        orchestrator.shutdown().await;
+        let handler = orchestrator.handler();
+        assert_matches!(handler.first().events.as_slice(), [FromOrchestrator::Shutdown]);
+        assert_matches!(handler.second().events.as_slice(), [FromOrchestrator::Shutdown]);
+    }
+
+    #[tokio::test]
+    async fn combined_handler_backfill_requests() {
+        let (first_target, second_target) = (B256::random(), B256::random());
+        let first = QueueHandler {
+            queue: VecDeque::from([HandlerEvent::BackfillAction(BackfillAction::Start(
+                first_target.into(),
+            ))]),
+            ..Default::default()
+        };
+        let second = QueueHandler {
+            queue: VecDeque::from([HandlerEvent::BackfillAction(BackfillAction::Start(
+                second_target.into(),
+            ))]),
+            ..Default::default()
+        };
+        let mut orchestrator = ChainOrchestrator::new(
+            CombinedHandler::new(first, second),
+            RecordingBackfillSync::default(),
+        );
+
+        // both requests are forwarded to the backfill sync in the order they were polled
+        assert!(futures::poll!(orchestrator.next()).is_pending());
+        assert_eq!(
+            orchestrator.backfill_sync.actions,
+            [
+                BackfillAction::Start(first_target.into()),
+                BackfillAction::Start(second_target.into())
+            ]
+        );
+    }
 }
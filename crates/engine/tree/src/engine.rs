@@ -6,7 +6,7 @@ use crate::{
     download::{BlockDownloader, DownloadAction, DownloadOutcome},
 };
 use alloy_primitives::B256;
-use futures::{Stream, StreamExt};
+use futures::{FutureExt, Stream, StreamExt};
 use reth_beacon_consensus::{BeaconConsensusEngineEvent, BeaconEngineMessage};
 use reth_chain_state::ExecutedBlock;
 use reth_engine_primitives::EngineTypes;
@@ -17,7 +17,7 @@ use std::{
     sync::mpsc::Sender,
     task::{ready, Context, Poll},
 };
-use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::{mpsc::UnboundedReceiver, oneshot};
 
 /// A [`ChainHandler`] that advances the chain based on incoming requests (CL engine API).
 ///
@@ -46,6 +46,10 @@ pub struct EngineHandler<T, S, D> {
     incoming_requests: S,
     /// A downloader to download blocks on demand.
     downloader: D,
+    /// Number of incoming requests delegated to the handler.
+    received_requests: u64,
+    /// Number of blocks downloaded on demand.
+    downloaded_blocks: u64,
 }
 
 impl<T, S, D> EngineHandler<T, S, D> {
@@ -55,7 +59,7 @@ impl<T, S, D> EngineHandler<T, S, D> {
     where
         T: EngineRequestHandler,
     {
-        Self { handler, incoming_requests, downloader }
+        Self { handler, incoming_requests, downloader, received_requests: 0, downloaded_blocks: 0 }
     }
 
     /// Returns a mutable reference to the request handler.
@@ -72,12 +76,17 @@ where
     D: BlockDownloader,
 {
     type Event = T::Event;
+    type Snapshot = EngineHandlerSnapshot;
 
     fn on_event(&mut self, event: FromOrchestrator) {
         // delegate event to the handler
         self.handler.on_event(event.into());
     }
 
+    fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.handler.poll_shutdown(cx)
+    }
+
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>> {
         loop {
             // drain the handler first
@@ -94,7 +103,9 @@ where
                                 // bubble up the event
                                 Poll::Ready(HandlerEvent::Event(ev))
                             }
-                            HandlerEvent::FatalError => Poll::Ready(HandlerEvent::FatalError),
+                            HandlerEvent::FatalError(err) => {
+                                Poll::Ready(HandlerEvent::FatalError(err))
+                            }
                         }
                     }
                     RequestHandlerEvent::Download(req) => {
@@ -107,6 +118,7 @@ where
             // pop the next incoming request
             if let Poll::Ready(Some(req)) = self.incoming_requests.poll_next_unpin(cx) {
                 // and delegate the request to the handler
+                self.received_requests += 1;
                 self.handler.on_event(FromEngine::Request(req.into()));
                 // skip downloading in this iteration to allow the handler to process the request
                 continue
@@ -115,6 +127,7 @@ where
             // advance the downloader
             if let Poll::Ready(DownloadOutcome::Blocks(blocks)) = self.downloader.poll(cx) {
                 // delegate the downloaded blocks to the handler
+                self.downloaded_blocks += blocks.len() as u64;
                 self.handler.on_event(FromEngine::DownloadedBlocks(blocks));
                 continue
             }
@@ -122,6 +135,22 @@ where
             return Poll::Pending
         }
     }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        EngineHandlerSnapshot {
+            received_requests: self.received_requests,
+            downloaded_blocks: self.downloaded_blocks,
+        }
+    }
+}
+
+/// Status of the [`EngineHandler`], see [`ChainHandler::snapshot`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EngineHandlerSnapshot {
+    /// Number of incoming requests delegated to the request handler.
+    pub received_requests: u64,
+    /// Number of blocks downloaded on demand.
+    pub downloaded_blocks: u64,
 }
 
 /// A type that processes incoming requests (e.g. requests from the consensus layer, engine API,
@@ -141,6 +170,12 @@ pub trait EngineRequestHandler: Send + Sync {
     /// Informs the handler about an event from the [`EngineHandler`].
     fn on_event(&mut self, event: FromEngine<Self::Request>);
 
+    /// Returns [`Poll::Ready`] once the handler completed its pending writes after receiving
+    /// [`FromOrchestrator::Shutdown`], see [`ChainHandler::poll_shutdown`].
+    fn poll_shutdown(&mut self, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
     /// Advances the handler.
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<RequestHandlerEvent<Self::Event>>;
 }
@@ -170,6 +205,8 @@ pub struct EngineApiRequestHandler<Request> {
     to_tree: Sender<FromEngine<Request>>,
     /// channel to receive messages from the tree.
     from_tree: UnboundedReceiver<EngineApiEvent>,
+    /// Notified by the tree once it completed the shutdown, if it's shutting down.
+    shutdown_rx: Option<oneshot::Receiver<()>>,
 }
 
 impl<Request> EngineApiRequestHandler<Request> {
@@ -178,7 +215,7 @@ impl<Request> EngineApiRequestHandler<Request> {
         to_tree: Sender<FromEngine<Request>>,
         from_tree: UnboundedReceiver<EngineApiEvent>,
     ) -> Self {
-        Self { to_tree, from_tree }
+        Self { to_tree, from_tree, shutdown_rx: None }
     }
 }
 
@@ -190,13 +227,31 @@ where
     type Request = Request;
 
     fn on_event(&mut self, event: FromEngine<Self::Request>) {
+        let event = match event {
+            FromEngine::Event(FromOrchestrator::Shutdown) => {
+                let (tx, rx) = oneshot::channel();
+                self.shutdown_rx = Some(rx);
+                FromEngine::Shutdown(tx)
+            }
+            event => event,
+        };
         // delegate to the tree
         let _ = self.to_tree.send(event);
     }
 
+    fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(rx) = &mut self.shutdown_rx else { return Poll::Ready(()) };
+        // an error means the tree exited, so there's nothing left to wait for
+        let _ = ready!(rx.poll_unpin(cx));
+        self.shutdown_rx = None;
+        Poll::Ready(())
+    }
+
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<RequestHandlerEvent<Self::Event>> {
         let Some(ev) = ready!(self.from_tree.poll_recv(cx)) else {
-            return Poll::Ready(RequestHandlerEvent::HandlerEvent(HandlerEvent::FatalError))
+            return Poll::Ready(RequestHandlerEvent::HandlerEvent(HandlerEvent::FatalError(
+                "engine tree channel closed".into(),
+            )))
         };
 
         let ev = match ev {
@@ -287,6 +342,9 @@ pub enum FromEngine<Req> {
     Request(Req),
     /// Downloaded blocks from the network.
     DownloadedBlocks(Vec<SealedBlockWithSenders>),
+    /// Same as [`FromOrchestrator::Shutdown`], but the sender is notified once the pending writes
+    /// are completed.
+    Shutdown(oneshot::Sender<()>),
 }
 
 impl<Req: Display> Display for FromEngine<Req> {
@@ -297,6 +355,7 @@ impl<Req: Display> Display for FromEngine<Req> {
             Self::DownloadedBlocks(blocks) => {
                 write!(f, "DownloadedBlocks({} blocks)", blocks.len())
             }
+            Self::Shutdown(_) => write!(f, "Shutdown"),
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::backfill::{BackfillAction, BackfillEvent, BackfillSync};
 use alloy_primitives::{Sealable, B256};
 use reth_chainspec::ChainSpec;
 use reth_network_p2p::test_utils::TestFullBlockClient;
@@ -10,7 +11,12 @@ use reth_prune_types::PruneModes;
 use reth_stages::{test_utils::TestStages, ExecOutput, StageError};
 use reth_stages_api::Pipeline;
 use reth_static_file::StaticFileProducer;
-use std::{collections::VecDeque, ops::Range, sync::Arc};
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    sync::Arc,
+    task::{Context, Poll},
+};
 use tokio::sync::watch;
 
 /// Test pipeline builder.
@@ -82,3 +88,15 @@ pub fn insert_headers_into_client(
         client.insert(sealed_header.clone(), body.clone());
     }
 }
+
+/// A backfill sync that is never started.
+#[derive(Debug)]
+pub struct IdleBackfillSync;
+
+impl BackfillSync for IdleBackfillSync {
+    fn on_action(&mut self, _action: BackfillAction) {}
+
+    fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<BackfillEvent> {
+        Poll::Pending
+    }
+}
@@ -1,5 +1,7 @@
 //! Engine tree configuration.
 
+use reth_beacon_consensus::MIN_BLOCKS_FOR_PIPELINE_RUN;
+
 /// Triggers persistence when the number of canonical blocks in memory exceeds this threshold.
 pub const DEFAULT_PERSISTENCE_THRESHOLD: u64 = 2;
 
@@ -32,6 +34,10 @@ pub struct TreeConfig {
     /// This is used as a cutoff to prevent long-running sequential block execution when we receive
     /// a batch of downloaded blocks.
     max_execute_block_batch_size: usize,
+    /// The largest gap between the local tip and the sync target for which live sync is used.
+    ///
+    /// If the gap exceeds this threshold, a backfill (pipeline) sync is triggered instead.
+    pipeline_run_threshold: u64,
 }
 
 impl Default for TreeConfig {
@@ -42,6 +48,7 @@ impl Default for TreeConfig {
             block_buffer_limit: DEFAULT_BLOCK_BUFFER_LIMIT,
             max_invalid_header_cache_length: DEFAULT_MAX_INVALID_HEADER_CACHE_LENGTH,
             max_execute_block_batch_size: DEFAULT_MAX_EXECUTE_BLOCK_BATCH_SIZE,
+            pipeline_run_threshold: MIN_BLOCKS_FOR_PIPELINE_RUN,
         }
     }
 }
@@ -54,6 +61,7 @@ impl TreeConfig {
         block_buffer_limit: u32,
         max_invalid_header_cache_length: u32,
         max_execute_block_batch_size: usize,
+        pipeline_run_threshold: u64,
     ) -> Self {
         Self {
             persistence_threshold,
@@ -61,6 +69,7 @@ impl TreeConfig {
             block_buffer_limit,
             max_invalid_header_cache_length,
             max_execute_block_batch_size,
+            pipeline_run_threshold,
         }
     }
 
@@ -89,6 +98,11 @@ impl TreeConfig {
         self.max_execute_block_batch_size
     }
 
+    /// Return the pipeline run threshold.
+    pub const fn pipeline_run_threshold(&self) -> u64 {
+        self.pipeline_run_threshold
+    }
+
     /// Setter for persistence threshold.
     pub const fn with_persistence_threshold(mut self, persistence_threshold: u64) -> Self {
         self.persistence_threshold = persistence_threshold;
@@ -127,4 +141,10 @@ impl TreeConfig {
         self.max_execute_block_batch_size = max_execute_block_batch_size;
         self
     }
+
+    /// Setter for pipeline run threshold.
+    pub const fn with_pipeline_run_threshold(mut self, pipeline_run_threshold: u64) -> Self {
+        self.pipeline_run_threshold = pipeline_run_threshold;
+        self
+    }
 }
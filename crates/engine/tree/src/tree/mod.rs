@@ -15,7 +15,7 @@ use alloy_rpc_types_engine::{
 };
 use reth_beacon_consensus::{
     BeaconConsensusEngineEvent, BeaconEngineMessage, ForkchoiceStateTracker, InvalidHeaderCache,
-    OnForkChoiceUpdated, MIN_BLOCKS_FOR_PIPELINE_RUN,
+    OnForkChoiceUpdated,
 };
 use reth_blockchain_tree::{
     error::{InsertBlockErrorKindTwo, InsertBlockErrorTwo, InsertBlockFatalError},
@@ -1187,6 +1187,10 @@ where
                 FromOrchestrator::BackfillSyncFinished(ctrl) => {
                     self.on_backfill_sync_finished(ctrl)?;
                 }
+                FromOrchestrator::Shutdown => {
+                    debug!(target: "engine::tree", "received shutdown event");
+                    self.on_shutdown();
+                }
             },
             FromEngine::Request(request) => {
                 match request {
@@ -1245,10 +1249,33 @@ where
                     self.on_tree_event(event)?;
                 }
             }
+            FromEngine::Shutdown(tx) => {
+                debug!(target: "engine::tree", "received shutdown request");
+                self.on_shutdown();
+                let _ = tx.send(());
+            }
         }
         Ok(())
     }
 
+    /// Invoked when the orchestrator is shutting down.
+    ///
+    /// Waits for the in-flight persistence task, if any, so that the node doesn't exit in the
+    /// middle of writing blocks to disk.
+    fn on_shutdown(&mut self) {
+        let Some((rx, start_time)) = self.persistence_state.rx.take() else { return };
+
+        debug!(target: "engine::tree", "waiting for persistence task to finish before shutdown");
+        match rx.blocking_recv() {
+            Ok(Some(BlockNumHash { hash, number })) => {
+                self.metrics.engine.persistence_duration.record(start_time.elapsed());
+                self.persistence_state.finish(hash, number);
+            }
+            Ok(None) => {}
+            Err(_) => warn!(target: "engine::tree", "persistence task dropped before shutdown"),
+        }
+    }
+
     /// Invoked if the backfill sync has finished to target.
     ///
     /// At this point we consider the block synced to the backfill target.
@@ -1792,12 +1819,12 @@ where
     }
 
     /// Returns true if the distance from the local tip to the block is greater than the configured
-    /// threshold.
+    /// [`TreeConfig::pipeline_run_threshold`].
     ///
     /// If the `local_tip` is greater than the `block`, then this will return false.
     #[inline]
     const fn exceeds_backfill_run_threshold(&self, local_tip: u64, block: u64) -> bool {
-        block > local_tip && block - local_tip > MIN_BLOCKS_FOR_PIPELINE_RUN
+        block > local_tip && block - local_tip > self.config.pipeline_run_threshold()
     }
 
     /// Returns how far the local tip is from the given block. If the local tip is at the same
@@ -2557,10 +2584,18 @@ pub enum AdvancePersistenceError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::persistence::PersistenceAction;
+    use crate::{
+        chain::ChainOrchestrator,
+        download::NoopBlockDownloader,
+        engine::{EngineApiRequestHandler, EngineHandler},
+        persistence::PersistenceAction,
+        test_utils::IdleBackfillSync,
+    };
     use alloy_primitives::{Bytes, Sealable};
     use alloy_rlp::Decodable;
-    use reth_beacon_consensus::{EthBeaconConsensus, ForkchoiceStatus};
+    use reth_beacon_consensus::{
+        EthBeaconConsensus, ForkchoiceStatus, MIN_BLOCKS_FOR_PIPELINE_RUN,
+    };
     use reth_chain_state::{test_utils::TestBlockBuilder, BlockState};
     use reth_chainspec::{ChainSpec, HOLESKY, MAINNET};
     use reth_ethereum_engine_primitives::EthEngineTypes;
@@ -2571,6 +2606,7 @@ mod tests {
     use std::{
         str::FromStr,
         sync::mpsc::{channel, Sender},
+        time::Duration,
     };
     use tokio::sync::mpsc::unbounded_channel;
 
@@ -3033,6 +3069,37 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_tree_shutdown_waits_for_persistence() {
+        reth_tracing::init_test_tracing();
+
+        let mut test_harness = TestHarness::new(MAINNET.clone());
+
+        // a persistence task is in flight
+        let (persistence_tx, persistence_rx) = oneshot::channel();
+        test_harness.tree.persistence_state.start(persistence_rx);
+
+        let TestHarness { tree, to_tree_tx, from_tree_rx, .. } = test_harness;
+        std::thread::Builder::new().name("Tree Task".to_string()).spawn(|| tree.run()).unwrap();
+
+        let handler = EngineHandler::new(
+            EngineApiRequestHandler::new(to_tree_tx, from_tree_rx),
+            NoopBlockDownloader::default(),
+            futures::stream::empty::<EngineApiRequest<EthEngineTypes>>(),
+        );
+        let mut orchestrator = ChainOrchestrator::new(handler, IdleBackfillSync);
+        let mut shutdown = std::pin::pin!(orchestrator.shutdown());
+
+        // the shutdown doesn't complete while the tree waits for the persistence task
+        assert!(tokio::time::timeout(Duration::from_millis(100), &mut shutdown).await.is_err());
+
+        // and completes once the persistence task finished
+        persistence_tx.send(Some(BlockNumHash::default())).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), shutdown)
+            .await
+            .expect("shutdown didn't complete after the persistence task finished");
+    }
+
     #[tokio::test]
     async fn test_in_memory_state_trait_impl() {
         let blocks: Vec<_> = TestBlockBuilder::default().get_executed_blocks(0..10).collect();
@@ -4035,4 +4102,27 @@ mod tests {
         let fork_tip_hash = side_chain.last().unwrap().hash();
         test_harness.send_fcu(fork_tip_hash, ForkchoiceStatus::Invalid).await;
     }
+
+    #[test]
+    fn test_pipeline_run_threshold() {
+        let chain_spec = MAINNET.clone();
+        let mut test_harness = TestHarness::new(chain_spec);
+        test_harness.tree.config = TreeConfig::default().with_pipeline_run_threshold(10);
+
+        let finalized_block_hash = B256::random();
+        test_harness.tree.state.forkchoice_state_tracker.set_latest(
+            ForkchoiceState {
+                head_block_hash: B256::random(),
+                safe_block_hash: B256::ZERO,
+                finalized_block_hash,
+            },
+            ForkchoiceStatus::Syncing,
+        );
+
+        // live sync while the gap is within the threshold
+        assert_eq!(test_harness.tree.backfill_sync_target(0, 10, None), None);
+        // pipeline sync once the gap exceeds the threshold
+        assert_eq!(test_harness.tree.backfill_sync_target(0, 11, None), Some(finalized_block_hash));
+        assert_eq!(test_harness.tree.backfill_sync_target(5, 15, None), None);
+    }
 }
@@ -288,8 +288,6 @@ where
     // apply eip-4788 pre block contract call
     apply_beacon_root_contract_call(
         evm_config,
-        chain_spec,
-        reorg_target.timestamp,
         reorg_target.number,
         reorg_target.parent_beacon_block_root,
         &mut evm,
@@ -14,10 +14,7 @@ use reth_evm::{
         BatchExecutor, BlockExecutionError, BlockExecutionInput, BlockExecutionOutput,
         BlockExecutorProvider, BlockValidationError, Executor, ProviderError,
     },
-    system_calls::{
-        apply_beacon_root_contract_call, apply_blockhashes_contract_call,
-        apply_consolidation_requests_contract_call, apply_withdrawal_requests_contract_call,
-    },
+    system_calls::SystemCaller,
     ConfigureEvm,
 };
 use reth_execution_types::ExecutionOutcome;
@@ -142,23 +139,10 @@ where
         DB: Database,
         DB::Error: Into<ProviderError> + Display,
     {
+        let system_caller = SystemCaller::new(&self.evm_config, &self.chain_spec);
+
         // apply pre execution changes
-        apply_beacon_root_contract_call(
-            &self.evm_config,
-            &self.chain_spec,
-            block.timestamp,
-            block.number,
-            block.parent_beacon_block_root,
-            &mut evm,
-        )?;
-        apply_blockhashes_contract_call(
-            &self.evm_config,
-            &self.chain_spec,
-            block.timestamp,
-            block.number,
-            block.parent_hash,
-            &mut evm,
-        )?;
+        system_caller.apply_pre_execution_changes(&block.header, &mut evm)?;
 
         // execute transactions
         let mut cumulative_gas_used = 0;
@@ -212,15 +196,11 @@ where
             let deposit_requests =
                 crate::eip6110::parse_deposits_from_receipts(&self.chain_spec, &receipts)?;
 
-            // Collect all EIP-7685 requests
-            let withdrawal_requests =
-                apply_withdrawal_requests_contract_call(&self.evm_config, &mut evm)?;
+            // Collect all EIP-7002 and EIP-7251 requests
+            let post_execution_requests =
+                system_caller.apply_post_execution_changes(&block.header, &mut evm)?;
 
-            // Collect all EIP-7251 requests
-            let consolidation_requests =
-                apply_consolidation_requests_contract_call(&self.evm_config, &mut evm)?;
-
-            [deposit_requests, withdrawal_requests, consolidation_requests].concat()
+            [deposit_requests, post_execution_requests].concat()
         } else {
             vec![]
         };
@@ -477,18 +457,35 @@ mod tests {
         eip2935::{HISTORY_STORAGE_ADDRESS, HISTORY_STORAGE_CODE},
         eip4788::{BEACON_ROOTS_ADDRESS, BEACON_ROOTS_CODE, SYSTEM_ADDRESS},
         eip7002::{WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS, WITHDRAWAL_REQUEST_PREDEPLOY_CODE},
+        eip7251::CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS,
     };
-    use alloy_primitives::{b256, fixed_bytes, keccak256, Bytes, TxKind, B256};
+    use alloy_primitives::{b256, fixed_bytes, keccak256, Address, Bytes, TxKind, B256};
     use reth_chainspec::{ChainSpecBuilder, ForkCondition};
+    use reth_evm::{
+        async_db::DatabaseAsync,
+        system_calls::{
+            pre_block_beacon_root_contract_call,
+            pre_block_beacon_root_contract_call_with_inspector,
+            pre_block_beacon_root_contract_call_with_precompiles,
+            simulate_beacon_root_contract_call, simulate_beacon_root_contract_call_async,
+            apply_beacon_root_contract_call, transact_system_contract_call,
+            GasScheduleOverride, SystemCaller,
+        },
+        ConfigureEvmEnv,
+    };
     use reth_primitives::{
         constants::{EMPTY_ROOT_HASH, ETH_TO_WEI},
         public_key_to_address, Account, Block, BlockBody, Transaction,
     };
     use reth_revm::{
-        database::StateProviderDatabase, test_utils::StateProviderTest, TransitionState,
+        database::StateProviderDatabase,
+        interpreter::{opcode, Interpreter},
+        precompile::{Precompile, PrecompileOutput, PrecompileResult, PrecompileWithAddress},
+        test_utils::StateProviderTest,
+        EvmContext, Inspector, TransitionState,
     };
     use reth_testing_utils::generators::{self, sign_tx_with_key_pair};
-    use revm_primitives::BLOCKHASH_SERVE_WINDOW;
+    use revm_primitives::{AccountInfo, Bytecode, SpecId, BLOCKHASH_SERVE_WINDOW};
     use secp256k1::{Keypair, Secp256k1};
     use std::collections::HashMap;
 
@@ -866,6 +863,430 @@ mod tests {
         assert_eq!(parent_beacon_block_root_storage, U256::from(0x69));
     }
 
+    #[test]
+    fn eip_4788_simulate() {
+        let header = Header {
+            timestamp: 1,
+            number: 1,
+            beneficiary: Address::with_last_byte(0xcb),
+            parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+            excess_blob_gas: Some(0),
+            ..Header::default()
+        };
+
+        let db = create_state_provider_with_beacon_root_contract();
+
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::from(&*MAINNET)
+                .shanghai_activated()
+                .with_fork(EthereumHardfork::Cancun, ForkCondition::Timestamp(1))
+                .build(),
+        );
+        let evm_config = EthEvmConfig::new(chain_spec);
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+        let res = simulate_beacon_root_contract_call(
+            &mut state,
+            &evm_config,
+            &cfg,
+            &block_env,
+            header.parent_beacon_block_root,
+        )
+        .unwrap()
+        .expect("cancun is active");
+
+        // the returned state contains the storage write
+        let history_buffer_length = 8191u64;
+        let timestamp_index = U256::from(header.timestamp % history_buffer_length);
+        assert_eq!(
+            res.state[&BEACON_ROOTS_ADDRESS].storage[&timestamp_index].present_value,
+            U256::from(header.timestamp)
+        );
+
+        // the system address and coinbase are stripped from the returned state
+        assert!(!res.state.contains_key(&SYSTEM_ADDRESS));
+        assert!(!res.state.contains_key(&header.beneficiary));
+
+        // nothing was committed to the database
+        assert_eq!(state.storage(BEACON_ROOTS_ADDRESS, timestamp_index).unwrap(), U256::ZERO);
+    }
+
+    /// An async database that yields to the runtime before every state load.
+    struct AsyncStateProviderTest(StateProviderDatabase<StateProviderTest>);
+
+    impl DatabaseAsync for AsyncStateProviderTest {
+        type Error = ProviderError;
+
+        async fn basic_async(
+            &mut self,
+            address: Address,
+        ) -> Result<Option<AccountInfo>, Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.basic(address)
+        }
+
+        async fn code_by_hash_async(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.code_by_hash(code_hash)
+        }
+
+        async fn storage_async(
+            &mut self,
+            address: Address,
+            index: U256,
+        ) -> Result<U256, Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.storage(address, index)
+        }
+
+        async fn block_hash_async(&mut self, number: u64) -> Result<B256, Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.block_hash(number)
+        }
+    }
+
+    #[tokio::test]
+    async fn eip_4788_simulate_async() {
+        let header = Header {
+            timestamp: 1,
+            number: 1,
+            parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+            excess_blob_gas: Some(0),
+            ..Header::default()
+        };
+
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::from(&*MAINNET)
+                .shanghai_activated()
+                .with_fork(EthereumHardfork::Cancun, ForkCondition::Timestamp(1))
+                .build(),
+        );
+        let evm_config = EthEvmConfig::new(chain_spec);
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        let sync_res = simulate_beacon_root_contract_call(
+            &mut StateProviderDatabase::new(create_state_provider_with_beacon_root_contract()),
+            &evm_config,
+            &cfg,
+            &block_env,
+            header.parent_beacon_block_root,
+        )
+        .unwrap()
+        .expect("cancun is active");
+
+        let async_res = simulate_beacon_root_contract_call_async(
+            AsyncStateProviderTest(StateProviderDatabase::new(
+                create_state_provider_with_beacon_root_contract(),
+            )),
+            evm_config,
+            cfg,
+            block_env,
+            header.parent_beacon_block_root,
+        )
+        .await
+        .unwrap()
+        .expect("cancun is active");
+
+        assert_eq!(async_res, sync_res);
+    }
+
+    #[test]
+    fn eip_4788_custom_precompile() {
+        // This test ensures that precompiles injected into the pre-block EVM can be called by the
+        // beacon root contract during the system call.
+        let precompile_address = Address::with_last_byte(0x99);
+
+        // CALL(gas, 0x99, 0, 0, 0, 0, 32), then SSTORE(0, MLOAD(0))
+        let code = Bytes::from_static(&[
+            0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x99, 0x5a, 0xf1,
+            0x50, 0x60, 0x00, 0x51, 0x60, 0x00, 0x55, 0x00,
+        ]);
+        let mut db = StateProviderTest::default();
+        db.insert_account(
+            BEACON_ROOTS_ADDRESS,
+            Account { balance: U256::ZERO, bytecode_hash: Some(keccak256(&code)), nonce: 1 },
+            Some(code),
+            HashMap::default(),
+        );
+
+        let header = Header {
+            timestamp: 1,
+            number: 1,
+            parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+            excess_blob_gas: Some(0),
+            ..Header::default()
+        };
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::from(&*MAINNET)
+                .shanghai_activated()
+                .with_fork(EthereumHardfork::Cancun, ForkCondition::Timestamp(1))
+                .build(),
+        );
+        let evm_config = EthEvmConfig::new(chain_spec);
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        fn precompile(_input: &Bytes, _gas_limit: u64) -> PrecompileResult {
+            Ok(PrecompileOutput::new(0, B256::with_last_byte(0x2a).into()))
+        }
+
+        // without the precompile the call returns no data and the slot stays empty
+        let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+        pre_block_beacon_root_contract_call(
+            &mut state,
+            &evm_config,
+            &cfg,
+            &block_env,
+            header.parent_beacon_block_root,
+            None,
+        )
+        .unwrap();
+        assert_eq!(state.storage(BEACON_ROOTS_ADDRESS, U256::ZERO).unwrap(), U256::ZERO);
+
+        // with the precompile injected the contract stores its output
+        let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+        pre_block_beacon_root_contract_call_with_precompiles(
+            &mut state,
+            &evm_config,
+            &cfg,
+            &block_env,
+            header.parent_beacon_block_root,
+            None,
+            [PrecompileWithAddress(precompile_address, Precompile::Standard(precompile))],
+        )
+        .unwrap();
+        assert_eq!(state.storage(BEACON_ROOTS_ADDRESS, U256::ZERO).unwrap(), U256::from(0x2a));
+    }
+
+    /// Records the contract address and the storage slot of every `SSTORE`.
+    #[derive(Debug, Default)]
+    struct SstoreInspector(Vec<(Address, U256)>);
+
+    impl<DB: Database> Inspector<DB> for SstoreInspector {
+        fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+            if interp.current_opcode() == opcode::SSTORE {
+                let slot = interp.stack().peek(0).unwrap();
+                self.0.push((interp.contract.target_address, slot));
+            }
+        }
+    }
+
+    #[test]
+    fn eip_4788_inspector() {
+        let header = Header {
+            timestamp: 1,
+            number: 1,
+            parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+            excess_blob_gas: Some(0),
+            ..Header::default()
+        };
+
+        let db = create_state_provider_with_beacon_root_contract();
+
+        let chain_spec = Arc::new(ChainSpecBuilder::from(&*MAINNET).cancun_activated().build());
+        let evm_config = EthEvmConfig::new(chain_spec);
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+        let inspector = pre_block_beacon_root_contract_call_with_inspector(
+            &mut state,
+            &evm_config,
+            &cfg,
+            &block_env,
+            header.parent_beacon_block_root,
+            SstoreInspector::default(),
+        )
+        .unwrap();
+
+        // the inspector observed the writes of the timestamp and the root
+        let history_buffer_length = 8191u64;
+        let timestamp_index = U256::from(header.timestamp % history_buffer_length);
+        let root_index = timestamp_index + U256::from(history_buffer_length);
+        assert_eq!(
+            inspector.0,
+            [(BEACON_ROOTS_ADDRESS, timestamp_index), (BEACON_ROOTS_ADDRESS, root_index)]
+        );
+
+        // the state changes are committed
+        assert_eq!(
+            state.storage(BEACON_ROOTS_ADDRESS, timestamp_index).unwrap(),
+            U256::from(header.timestamp)
+        );
+    }
+
+    #[test]
+    fn eip_4788_gas_schedule_override() {
+        // SLOAD(0), then SSTORE(0, GAS)
+        let code = Bytes::from_static(&[0x60, 0x00, 0x54, 0x50, 0x5a, 0x60, 0x00, 0x55, 0x00]);
+        let mut db = StateProviderTest::default();
+        db.insert_account(
+            BEACON_ROOTS_ADDRESS,
+            Account { balance: U256::ZERO, bytecode_hash: Some(keccak256(&code)), nonce: 1 },
+            Some(code),
+            HashMap::default(),
+        );
+
+        let header = Header {
+            timestamp: 1,
+            number: 1,
+            parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+            excess_blob_gas: Some(0),
+            ..Header::default()
+        };
+        let chain_spec = Arc::new(ChainSpecBuilder::from(&*MAINNET).cancun_activated().build());
+        let evm_config = EthEvmConfig::new(chain_spec);
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        // returns the gas left after the SLOAD, as stored by the contract
+        let gas_left = |gas_schedule: Option<&GasScheduleOverride>| {
+            let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+            pre_block_beacon_root_contract_call(
+                &mut state,
+                &evm_config,
+                &cfg,
+                &block_env,
+                header.parent_beacon_block_root,
+                gas_schedule,
+            )
+            .unwrap();
+            state.storage(BEACON_ROOTS_ADDRESS, U256::ZERO).unwrap()
+        };
+
+        let default_gas_left = gas_left(None);
+        // an empty override keeps the gas costs of the spec
+        assert_eq!(gas_left(Some(&GasScheduleOverride::new())), default_gas_left);
+
+        // the cold SLOAD costs 2100 gas in Cancun, the override charges 800 instead
+        let gas_schedule = GasScheduleOverride::new().with_opcode_cost(0x54, 800);
+        assert_eq!(gas_left(Some(&gas_schedule)), default_gas_left + U256::from(2100 - 800));
+    }
+
+    #[test]
+    #[should_panic(expected = "gas cost of opcode 0xf1 can't be overridden")]
+    fn gas_schedule_override_rejects_frame_opcodes() {
+        // SLOAD continues the execution of the frame, CALL starts a new one
+        assert!(GasScheduleOverride::is_supported_opcode(0x54));
+        assert!(!GasScheduleOverride::is_supported_opcode(0xf1));
+
+        let _ = GasScheduleOverride::new().with_opcode_cost(0xf1, 100);
+    }
+
+    #[test]
+    fn eip_4788_spec_id_cancun_boundary() {
+        let db = create_state_provider_with_beacon_root_contract();
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::from(&*MAINNET)
+                .shanghai_activated()
+                .with_fork(EthereumHardfork::Cancun, ForkCondition::Timestamp(2))
+                .build(),
+        );
+        let evm_config = EthEvmConfig::new(chain_spec);
+
+        for (timestamp, spec_id_override, expected_spec_id) in [
+            (1, None, SpecId::SHANGHAI),
+            (2, None, SpecId::CANCUN),
+            (3, None, SpecId::CANCUN),
+            // the guard follows the EVM, even if its spec doesn't match the chain spec
+            (1, Some(SpecId::CANCUN), SpecId::CANCUN),
+        ] {
+            let header = Header {
+                timestamp,
+                number: 1,
+                parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+                excess_blob_gas: Some(0),
+                ..Header::default()
+            };
+            let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+            let mut block_env = BlockEnv::default();
+            evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+            let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+            let mut evm = evm_config.evm_with_env(
+                &mut state,
+                EnvWithHandlerCfg::new_with_cfg_env(cfg, block_env, Default::default()),
+            );
+            if let Some(spec_id) = spec_id_override {
+                evm.modify_spec_id(spec_id);
+            }
+            assert_eq!(evm.spec_id(), expected_spec_id);
+
+            apply_beacon_root_contract_call(
+                &evm_config,
+                header.number,
+                header.parent_beacon_block_root,
+                &mut evm,
+            )
+            .unwrap();
+            drop(evm);
+
+            // the beacon root contract stores the timestamp only if Cancun is enabled in the EVM
+            state.basic(BEACON_ROOTS_ADDRESS).unwrap();
+            let timestamp_index = U256::from(timestamp % 8191);
+            assert_eq!(
+                state.storage(BEACON_ROOTS_ADDRESS, timestamp_index).unwrap() ==
+                    U256::from(timestamp),
+                expected_spec_id.is_enabled_in(SpecId::CANCUN),
+                "timestamp {timestamp}, spec {expected_spec_id:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn system_contract_call_arbitrary_address() {
+        let contract_address = Address::with_last_byte(0x42);
+
+        // SSTORE(0, CALLDATALOAD(0))
+        let code = Bytes::from_static(&[0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00]);
+        let mut db = StateProviderTest::default();
+        db.insert_account(
+            contract_address,
+            Account { balance: U256::ZERO, bytecode_hash: Some(keccak256(&code)), nonce: 1 },
+            Some(code),
+            HashMap::default(),
+        );
+
+        let chain_spec = Arc::new(ChainSpecBuilder::from(&*MAINNET).cancun_activated().build());
+        let evm_config = EthEvmConfig::new(chain_spec);
+        let header =
+            Header { timestamp: 1, number: 1, excess_blob_gas: Some(0), ..Header::default() };
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+        let mut evm = evm_config.evm_with_env(
+            &mut state,
+            EnvWithHandlerCfg::new_with_cfg_env(cfg, block_env, Default::default()),
+        );
+        let previous_env = evm.context.evm.env.clone();
+
+        let input = B256::with_last_byte(0x69);
+        let res =
+            transact_system_contract_call(&evm_config, &mut evm, contract_address, input.into())
+                .unwrap();
+
+        // the input was stored by the contract and the system address is not touched
+        assert!(res.result.is_success());
+        assert_eq!(
+            res.state[&contract_address].storage[&U256::ZERO].present_value,
+            U256::from_be_bytes(input.0)
+        );
+        assert!(!res.state.contains_key(&SYSTEM_ADDRESS));
+
+        // the previous env is restored
+        assert_eq!(evm.context.evm.env, previous_env);
+    }
+
     /// Create a state provider with blockhashes and the EIP-2935 system contract.
     fn create_state_provider_with_block_hashes(latest_block: u64) -> StateProviderTest {
         let mut db = StateProviderTest::default();
@@ -941,7 +1362,7 @@ mod tests {
 
         let chain_spec = Arc::new(
             ChainSpecBuilder::from(&*MAINNET)
-                .shanghai_activated()
+                .cancun_activated()
                 .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(0))
                 .build(),
         );
@@ -986,7 +1407,7 @@ mod tests {
 
         let chain_spec = Arc::new(
             ChainSpecBuilder::from(&*MAINNET)
-                .shanghai_activated()
+                .cancun_activated()
                 .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(1))
                 .build(),
         );
@@ -996,6 +1417,7 @@ mod tests {
             timestamp: 1,
             number: fork_activation_block,
             requests_root: Some(EMPTY_ROOT_HASH),
+            parent_beacon_block_root: Some(B256::ZERO),
             ..Header::default()
         };
         let provider = executor_provider(chain_spec);
@@ -1042,7 +1464,7 @@ mod tests {
 
         let chain_spec = Arc::new(
             ChainSpecBuilder::from(&*MAINNET)
-                .shanghai_activated()
+                .cancun_activated()
                 .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(1))
                 .build(),
         );
@@ -1055,6 +1477,7 @@ mod tests {
             timestamp: 1,
             number: fork_activation_block,
             requests_root: Some(EMPTY_ROOT_HASH),
+            parent_beacon_block_root: Some(B256::ZERO),
             ..Header::default()
         };
 
@@ -1094,7 +1517,7 @@ mod tests {
 
         let chain_spec = Arc::new(
             ChainSpecBuilder::from(&*MAINNET)
-                .shanghai_activated()
+                .cancun_activated()
                 .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(0))
                 .build(),
         );
@@ -1139,6 +1562,7 @@ mod tests {
             timestamp: 1,
             number: 1,
             requests_root: Some(EMPTY_ROOT_HASH),
+            parent_beacon_block_root: Some(B256::ZERO),
             ..Header::default()
         };
         let header_hash = header.hash_slow();
@@ -1176,6 +1600,7 @@ mod tests {
             timestamp: 1,
             number: 2,
             requests_root: Some(EMPTY_ROOT_HASH),
+            parent_beacon_block_root: Some(B256::ZERO),
             ..Header::default()
         };
 
@@ -1215,7 +1640,7 @@ mod tests {
     fn eip_7002() {
         let chain_spec = Arc::new(
             ChainSpecBuilder::from(&*MAINNET)
-                .shanghai_activated()
+                .cancun_activated()
                 .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(0))
                 .build(),
         );
@@ -1293,7 +1718,7 @@ mod tests {
         // Create a chain specification with fork conditions set for Prague
         let chain_spec = Arc::new(
             ChainSpecBuilder::from(&*MAINNET)
-                .shanghai_activated()
+                .cancun_activated()
                 .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(0))
                 .build(),
         );
@@ -1371,4 +1796,96 @@ mod tests {
             ),
         }
     }
+
+    /// Applies the system calls of a block at timestamp 1 with the given chain spec and returns
+    /// the beacon root storage, the block hash storage and the collected requests.
+    fn apply_system_calls(chain_spec: Arc<ChainSpec>) -> (U256, U256, Vec<Request>) {
+        let mut db = create_state_provider_with_beacon_root_contract();
+        db.insert_account(
+            HISTORY_STORAGE_ADDRESS,
+            Account {
+                balance: U256::ZERO,
+                bytecode_hash: Some(keccak256(HISTORY_STORAGE_CODE.clone())),
+                nonce: 1,
+            },
+            Some(HISTORY_STORAGE_CODE.clone()),
+            HashMap::default(),
+        );
+        // request contracts that return a single zeroed request: RETURN(0, size)
+        for (address, size) in [
+            (WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS, 76),
+            (CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS, 116),
+        ] {
+            let code = Bytes::from(vec![0x60, size, 0x60, 0x00, 0xf3]);
+            db.insert_account(
+                address,
+                Account { balance: U256::ZERO, bytecode_hash: Some(keccak256(&code)), nonce: 1 },
+                Some(code),
+                HashMap::default(),
+            );
+        }
+
+        let header = Header {
+            timestamp: 1,
+            number: 1,
+            parent_hash: B256::with_last_byte(0x42),
+            parent_beacon_block_root: Some(B256::with_last_byte(0x69)),
+            excess_blob_gas: Some(0),
+            ..Header::default()
+        };
+        let evm_config = EthEvmConfig::new(chain_spec.clone());
+        let mut cfg = CfgEnvWithHandlerCfg::new(Default::default(), Default::default());
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_cfg_and_block_env(&mut cfg, &mut block_env, &header, U256::ZERO);
+
+        let mut state = State::builder().with_database(StateProviderDatabase::new(&db)).build();
+        let mut evm = evm_config.evm_with_env(
+            &mut state,
+            EnvWithHandlerCfg::new_with_cfg_env(cfg, block_env, Default::default()),
+        );
+
+        let system_caller = SystemCaller::new(&evm_config, &chain_spec);
+        system_caller.apply_pre_execution_changes(&header, &mut evm).unwrap();
+        let requests = system_caller.apply_post_execution_changes(&header, &mut evm).unwrap();
+        drop(evm);
+
+        // the contracts must be loaded before accessing their storage
+        state.basic(BEACON_ROOTS_ADDRESS).unwrap();
+        state.basic(HISTORY_STORAGE_ADDRESS).unwrap();
+        let beacon_root = state.storage(BEACON_ROOTS_ADDRESS, U256::from(1 + 8191)).unwrap();
+        let block_hash = state.storage(HISTORY_STORAGE_ADDRESS, U256::ZERO).unwrap();
+        (beacon_root, block_hash, requests)
+    }
+
+    #[test]
+    fn system_caller_active_forks() {
+        // pre-Cancun: no system calls
+        let chain_spec = Arc::new(ChainSpecBuilder::from(&*MAINNET).shanghai_activated().build());
+        let (beacon_root, block_hash, requests) = apply_system_calls(chain_spec);
+        assert_eq!(beacon_root, U256::ZERO);
+        assert_eq!(block_hash, U256::ZERO);
+        assert!(requests.is_empty());
+
+        // Cancun: only the beacon root contract call
+        let chain_spec = Arc::new(ChainSpecBuilder::from(&*MAINNET).cancun_activated().build());
+        let (beacon_root, block_hash, requests) = apply_system_calls(chain_spec);
+        assert_eq!(beacon_root, U256::from(0x69));
+        assert_eq!(block_hash, U256::ZERO);
+        assert!(requests.is_empty());
+
+        // Prague: all system calls
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::from(&*MAINNET)
+                .cancun_activated()
+                .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(0))
+                .build(),
+        );
+        let (beacon_root, block_hash, requests) = apply_system_calls(chain_spec);
+        assert_eq!(beacon_root, U256::from(0x69));
+        assert_eq!(block_hash, U256::from(0x42));
+        assert!(matches!(
+            requests.as_slice(),
+            [Request::WithdrawalRequest(_), Request::ConsolidationRequest(_)]
+        ));
+    }
 }
@@ -177,6 +177,27 @@ impl Chain {
         self.blocks_iter().zip(self.block_receipts_iter())
     }
 
+    /// Returns an iterator over all addresses touched by the chain.
+    ///
+    /// An address is considered touched if it's a sender or a recipient of a transaction, an
+    /// emitter of a log in the receipts, or an account changed in the bundle state (e.g. by an
+    /// internal call, a withdrawal or a block reward). The iterator may yield the same address
+    /// multiple times.
+    pub fn touched_addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        let transaction_addresses = self.blocks_iter().flat_map(|block| {
+            block.senders.iter().copied().chain(block.body.transactions().filter_map(|tx| tx.to()))
+        });
+        let log_addresses = self
+            .block_receipts_iter()
+            .flatten()
+            .flatten()
+            .flat_map(|receipt| receipt.logs.iter().map(|log| log.address));
+
+        let state_addresses = self.execution_outcome.bundle.state.keys().copied();
+
+        transaction_addresses.chain(log_addresses).chain(state_addresses)
+    }
+
     /// Get the block at which this chain forked.
     #[track_caller]
     pub fn fork_block(&self) -> ForkBlock {
@@ -643,6 +664,34 @@ mod tests {
         assert_eq!(chain.clone().split(0u64.into()), ChainSplit::NoSplitPending(chain));
     }
 
+    #[test]
+    fn touched_addresses() {
+        let sender = Address::new([1; 20]);
+        // An account that was only changed in the state, e.g. by an internal call or a withdrawal
+        let state_address = Address::new([2; 20]);
+
+        let mut block = SealedBlockWithSenders::default();
+        block.set_block_number(1);
+        block.senders.push(sender);
+
+        let execution_outcome = ExecutionOutcome::new(
+            BundleState::new(
+                vec![(state_address, None, Some(AccountInfo::default()), HashMap::default())],
+                vec![vec![(state_address, None, vec![])]],
+                vec![],
+            ),
+            vec![vec![]].into(),
+            1,
+            vec![],
+        );
+
+        let chain = Chain::new(vec![block], execution_outcome, None);
+
+        let touched_addresses = chain.touched_addresses().collect::<Vec<_>>();
+        assert!(touched_addresses.contains(&sender));
+        assert!(touched_addresses.contains(&state_address));
+    }
+
     #[test]
     fn receipts_by_block_hash() {
         // Create a default SealedBlockWithSenders object
@@ -0,0 +1,87 @@
+//! Async database support for the EVM.
+//!
+//! The EVM itself is synchronous, so an async database is driven by blocking on its futures with
+//! a [`tokio`] runtime [`Handle`]. The EVM must therefore run on a thread where blocking is
+//! allowed, e.g. inside [`tokio::task::spawn_blocking`].
+
+use alloy_primitives::{Address, B256, U256};
+use core::future::Future;
+use revm::Database;
+use revm_primitives::{AccountInfo, Bytecode};
+use tokio::runtime::Handle;
+
+/// An async version of [`Database`], e.g. backed by a remote state provider.
+pub trait DatabaseAsync {
+    /// The database error type.
+    type Error;
+
+    /// Gets basic account information.
+    fn basic_async(
+        &mut self,
+        address: Address,
+    ) -> impl Future<Output = Result<Option<AccountInfo>, Self::Error>> + Send;
+
+    /// Gets account code by its hash.
+    fn code_by_hash_async(
+        &mut self,
+        code_hash: B256,
+    ) -> impl Future<Output = Result<Bytecode, Self::Error>> + Send;
+
+    /// Gets storage value of address at index.
+    fn storage_async(
+        &mut self,
+        address: Address,
+        index: U256,
+    ) -> impl Future<Output = Result<U256, Self::Error>> + Send;
+
+    /// Gets block hash by block number.
+    fn block_hash_async(
+        &mut self,
+        number: u64,
+    ) -> impl Future<Output = Result<B256, Self::Error>> + Send;
+}
+
+/// Wraps a [`DatabaseAsync`] to be used as a [`Database`].
+///
+/// Every state load blocks the current thread until the future of the async database resolves.
+///
+/// # Panics
+///
+/// The [`Database`] methods panic if called from an asynchronous execution context.
+#[derive(Debug)]
+pub struct WrapDatabaseAsync<T> {
+    db: T,
+    handle: Handle,
+}
+
+impl<T> WrapDatabaseAsync<T> {
+    /// Wraps the given async database, driving its futures with the given runtime handle.
+    pub const fn new(db: T, handle: Handle) -> Self {
+        Self { db, handle }
+    }
+
+    /// Consumes the wrapper and returns the inner async database.
+    pub fn into_inner(self) -> T {
+        self.db
+    }
+}
+
+impl<T: DatabaseAsync> Database for WrapDatabaseAsync<T> {
+    type Error = T::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.handle.block_on(self.db.basic_async(address))
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.handle.block_on(self.db.code_by_hash_async(code_hash))
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.handle.block_on(self.db.storage_async(address, index))
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.handle.block_on(self.db.block_hash_async(number))
+    }
+}
@@ -20,6 +20,8 @@ use reth_primitives_traits::BlockHeader;
 use revm::{Database, Evm, GetInspector};
 use revm_primitives::{BlockEnv, CfgEnvWithHandlerCfg, Env, EnvWithHandlerCfg, SpecId, TxEnv};
 
+#[cfg(feature = "async")]
+pub mod async_db;
 pub mod builder;
 pub mod either;
 pub mod execute;
@@ -9,7 +9,9 @@ use core::fmt::Display;
 use reth_chainspec::EthereumHardforks;
 use reth_execution_errors::{BlockExecutionError, BlockValidationError};
 use reth_primitives::Header;
-use revm::{interpreter::Host, Database, DatabaseCommit, Evm};
+use revm::{
+    inspector_handle_register, interpreter::Host, Database, DatabaseCommit, Evm, GetInspector,
+};
 use revm_primitives::{BlockEnv, CfgEnvWithHandlerCfg, EnvWithHandlerCfg, ResultAndState};
 
 /// Apply the [EIP-2935](https://eips.ethereum.org/EIPS/eip-2935) pre block contract call.
@@ -52,6 +54,50 @@ where
     )
 }
 
+/// Apply the [EIP-2935](https://eips.ethereum.org/EIPS/eip-2935) pre block contract call with the
+/// given inspector.
+///
+/// Same as [`pre_block_blockhashes_contract_call`], but the [`Evm`] is built with the inspector as
+/// its external context, so that the system call can be traced. The inspector is returned after
+/// the call.
+pub fn pre_block_blockhashes_contract_call_with_inspector<'a, EvmConfig, DB, I>(
+    db: &'a mut DB,
+    evm_config: &EvmConfig,
+    chain_spec: impl EthereumHardforks,
+    initialized_cfg: &CfgEnvWithHandlerCfg,
+    initialized_block_env: &BlockEnv,
+    parent_block_hash: B256,
+    inspector: I,
+) -> Result<I, BlockExecutionError>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Display,
+    EvmConfig: ConfigureEvm<Header = Header>,
+    I: GetInspector<&'a mut DB>,
+{
+    let mut evm_pre_block = Evm::builder()
+        .with_db(db)
+        .with_external_context(inspector)
+        .with_env_with_handler_cfg(EnvWithHandlerCfg::new_with_cfg_env(
+            initialized_cfg.clone(),
+            initialized_block_env.clone(),
+            Default::default(),
+        ))
+        .append_handler_register(inspector_handle_register)
+        .build();
+
+    apply_blockhashes_contract_call(
+        evm_config,
+        chain_spec,
+        initialized_block_env.timestamp.to(),
+        initialized_block_env.number.to(),
+        parent_block_hash,
+        &mut evm_pre_block,
+    )?;
+
+    Ok(evm_pre_block.into_context().external)
+}
+
 /// Applies the pre-block call to the [EIP-2935] blockhashes contract, using the given block,
 /// chain specification, and EVM.
 ///
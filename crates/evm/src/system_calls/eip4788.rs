@@ -1,70 +1,246 @@
 //! [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) system call implementation.
-use alloc::{boxed::Box, string::ToString};
+use alloc::{boxed::Box, string::ToString, sync::Arc, vec::Vec};
 
-use crate::ConfigureEvm;
+use crate::{
+    system_calls::{transact_system_contract_call, GasScheduleOverride},
+    ConfigureEvm,
+};
 use alloy_eips::eip4788::BEACON_ROOTS_ADDRESS;
 use alloy_primitives::B256;
-use reth_chainspec::EthereumHardforks;
 use reth_execution_errors::{BlockExecutionError, BlockValidationError};
 use reth_primitives::Header;
-use revm::{interpreter::Host, Database, DatabaseCommit, Evm};
-use revm_primitives::{BlockEnv, CfgEnvWithHandlerCfg, EnvWithHandlerCfg, ResultAndState};
+use revm::{
+    inspector_handle_register, precompile::PrecompileWithAddress, Database, DatabaseCommit, Evm,
+    GetInspector,
+};
+use revm_primitives::{BlockEnv, CfgEnvWithHandlerCfg, EnvWithHandlerCfg, ResultAndState, SpecId};
 
 /// Apply the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) pre block contract call.
 ///
 /// This constructs a new [`Evm`] with the given DB, and environment
 /// ([`CfgEnvWithHandlerCfg`] and [`BlockEnv`]) to execute the pre block contract call.
 ///
+/// If a [`GasScheduleOverride`] is given, the system call is charged according to it instead of
+/// the gas costs of the configured spec. This is useful for chains with non-standard gas rules,
+/// e.g. for `SLOAD` and `SSTORE`.
+///
 /// This uses [`apply_beacon_root_contract_call`] to ultimately apply the beacon root contract state
 /// change.
 pub fn pre_block_beacon_root_contract_call<EvmConfig, DB>(
     db: &mut DB,
     evm_config: &EvmConfig,
-    chain_spec: impl EthereumHardforks,
     initialized_cfg: &CfgEnvWithHandlerCfg,
     initialized_block_env: &BlockEnv,
     parent_beacon_block_root: Option<B256>,
+    gas_schedule: Option<&GasScheduleOverride>,
+) -> Result<(), BlockExecutionError>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: core::fmt::Display,
+    EvmConfig: ConfigureEvm<Header = Header>,
+{
+    pre_block_beacon_root_contract_call_with_precompiles(
+        db,
+        evm_config,
+        initialized_cfg,
+        initialized_block_env,
+        parent_beacon_block_root,
+        gas_schedule,
+        [],
+    )
+}
+
+/// Apply the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) pre block contract call with
+/// additional precompiles.
+///
+/// Same as [`pre_block_beacon_root_contract_call`], but the given precompiles are added on top of
+/// the precompiles of the configured spec, so that they can be invoked during the system call.
+/// This is useful for chains that have custom predeploys relying on non-standard precompiles.
+///
+/// If no precompiles are provided, the EVM is constructed exactly as in
+/// [`pre_block_beacon_root_contract_call`].
+#[allow(clippy::too_many_arguments)]
+pub fn pre_block_beacon_root_contract_call_with_precompiles<EvmConfig, DB>(
+    db: &mut DB,
+    evm_config: &EvmConfig,
+    initialized_cfg: &CfgEnvWithHandlerCfg,
+    initialized_block_env: &BlockEnv,
+    parent_beacon_block_root: Option<B256>,
+    gas_schedule: Option<&GasScheduleOverride>,
+    precompiles: impl IntoIterator<Item = PrecompileWithAddress>,
 ) -> Result<(), BlockExecutionError>
 where
     DB: Database + DatabaseCommit,
     DB::Error: core::fmt::Display,
     EvmConfig: ConfigureEvm<Header = Header>,
 {
+    let precompiles = precompiles.into_iter().collect::<Vec<_>>();
+
     // apply pre-block EIP-4788 contract call
+    let mut builder =
+        Evm::builder().with_db(db).with_env_with_handler_cfg(EnvWithHandlerCfg::new_with_cfg_env(
+            initialized_cfg.clone(),
+            initialized_block_env.clone(),
+            Default::default(),
+        ));
+    if let Some(gas_schedule) = gas_schedule.filter(|schedule| !schedule.is_empty()) {
+        builder = builder.append_handler_register_box(gas_schedule.handler_register());
+    }
+    let mut evm_pre_block = if precompiles.is_empty() {
+        builder.build()
+    } else {
+        builder
+            .append_handler_register_box(Box::new(move |handler| {
+                // extend the precompiles loaded for the spec instead of replacing them
+                let load_precompiles = handler.pre_execution.load_precompiles.clone();
+                let precompiles = precompiles.clone();
+                handler.pre_execution.load_precompiles = Arc::new(move || {
+                    let mut loaded = load_precompiles();
+                    loaded.extend(precompiles.clone());
+                    loaded
+                });
+            }))
+            .build()
+    };
+
+    // initialize a block from the env, because the pre block call needs the block itself
+    apply_beacon_root_contract_call(
+        evm_config,
+        initialized_block_env.number.to(),
+        parent_beacon_block_root,
+        &mut evm_pre_block,
+    )
+}
+
+/// Apply the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) pre block contract call with the
+/// given inspector.
+///
+/// Same as [`pre_block_beacon_root_contract_call`], but the [`Evm`] is built with the inspector as
+/// its external context, so that the system call can be traced. The inspector is returned after
+/// the call.
+pub fn pre_block_beacon_root_contract_call_with_inspector<'a, EvmConfig, DB, I>(
+    db: &'a mut DB,
+    evm_config: &EvmConfig,
+    initialized_cfg: &CfgEnvWithHandlerCfg,
+    initialized_block_env: &BlockEnv,
+    parent_beacon_block_root: Option<B256>,
+    inspector: I,
+) -> Result<I, BlockExecutionError>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: core::fmt::Display,
+    EvmConfig: ConfigureEvm<Header = Header>,
+    I: GetInspector<&'a mut DB>,
+{
     let mut evm_pre_block = Evm::builder()
         .with_db(db)
+        .with_external_context(inspector)
         .with_env_with_handler_cfg(EnvWithHandlerCfg::new_with_cfg_env(
             initialized_cfg.clone(),
             initialized_block_env.clone(),
             Default::default(),
         ))
+        .append_handler_register(inspector_handle_register)
         .build();
 
-    // initialize a block from the env, because the pre block call needs the block itself
     apply_beacon_root_contract_call(
         evm_config,
-        chain_spec,
-        initialized_block_env.timestamp.to(),
         initialized_block_env.number.to(),
         parent_beacon_block_root,
         &mut evm_pre_block,
+    )?;
+
+    Ok(evm_pre_block.into_context().external)
+}
+
+/// Simulates the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) pre block contract call.
+///
+/// Same as [`pre_block_beacon_root_contract_call`], but the state changes are not committed to
+/// the database. Instead, the [`ResultAndState`] of the call is returned, so that its effects can
+/// be applied to a separate state, e.g. when simulating calls in the context of a block.
+///
+/// Returns `None` if Cancun is not enabled in the spec of the configured environment or the block
+/// is the genesis block.
+pub fn simulate_beacon_root_contract_call<EvmConfig, DB>(
+    db: &mut DB,
+    evm_config: &EvmConfig,
+    initialized_cfg: &CfgEnvWithHandlerCfg,
+    initialized_block_env: &BlockEnv,
+    parent_beacon_block_root: Option<B256>,
+) -> Result<Option<ResultAndState>, BlockExecutionError>
+where
+    DB: Database,
+    DB::Error: core::fmt::Display,
+    EvmConfig: ConfigureEvm<Header = Header>,
+{
+    let mut evm = Evm::builder()
+        .with_db(db)
+        .with_env_with_handler_cfg(EnvWithHandlerCfg::new_with_cfg_env(
+            initialized_cfg.clone(),
+            initialized_block_env.clone(),
+            Default::default(),
+        ))
+        .build();
+
+    transact_beacon_root_contract_call(
+        evm_config,
+        initialized_cfg.handler_cfg.spec_id,
+        initialized_block_env.number.to(),
+        parent_beacon_block_root,
+        &mut evm,
     )
 }
 
+/// Simulates the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) pre block contract call
+/// against an async database.
+///
+/// Same as [`simulate_beacon_root_contract_call`], but the state is loaded from the given
+/// [`DatabaseAsync`](crate::async_db::DatabaseAsync). The call is executed on a blocking task, so
+/// that awaiting the state loads doesn't block the async runtime.
+#[cfg(feature = "async")]
+pub async fn simulate_beacon_root_contract_call_async<EvmConfig, DB>(
+    db: DB,
+    evm_config: EvmConfig,
+    initialized_cfg: CfgEnvWithHandlerCfg,
+    initialized_block_env: BlockEnv,
+    parent_beacon_block_root: Option<B256>,
+) -> Result<Option<ResultAndState>, BlockExecutionError>
+where
+    DB: crate::async_db::DatabaseAsync + Send + 'static,
+    DB::Error: core::fmt::Display,
+    EvmConfig: ConfigureEvm<Header = Header>,
+{
+    let handle = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let mut db = crate::async_db::WrapDatabaseAsync::new(db, handle);
+        simulate_beacon_root_contract_call(
+            &mut db,
+            &evm_config,
+            &initialized_cfg,
+            &initialized_block_env,
+            parent_beacon_block_root,
+        )
+    })
+    .await
+    .map_err(BlockExecutionError::other)?
+}
+
 /// Applies the pre-block call to the [EIP-4788] beacon block root contract, using the given block,
-/// chain spec, EVM.
+/// spec, EVM.
+///
+/// The spec must be the spec the EVM is configured with, e.g. [`Evm::spec_id`], so that the fork
+/// check is consistent with the rules the call is executed under.
 ///
 /// Note: this does not commit the state changes to the database, it only transact the call.
 ///
-/// Returns `None` if Cancun is not active or the block is the genesis block, otherwise returns the
-/// result of the call.
+/// Returns `None` if Cancun is not enabled in the spec or the block is the genesis block,
+/// otherwise returns the result of the call.
 ///
 /// [EIP-4788]: https://eips.ethereum.org/EIPS/eip-4788
 #[inline]
-pub fn transact_beacon_root_contract_call<EvmConfig, EXT, DB, Spec>(
+pub fn transact_beacon_root_contract_call<EvmConfig, EXT, DB>(
     evm_config: &EvmConfig,
-    chain_spec: &Spec,
-    block_timestamp: u64,
+    spec_id: SpecId,
     block_number: u64,
     parent_beacon_block_root: Option<B256>,
     evm: &mut Evm<'_, EXT, DB>,
@@ -73,9 +249,8 @@ where
     DB: Database,
     DB::Error: core::fmt::Display,
     EvmConfig: ConfigureEvm<Header = Header>,
-    Spec: EthereumHardforks,
 {
-    if !chain_spec.is_cancun_active_at_timestamp(block_timestamp) {
+    if !spec_id.is_enabled_in(SpecId::CANCUN) {
         return Ok(None)
     }
 
@@ -94,50 +269,30 @@ where
         return Ok(None)
     }
 
-    // get previous env
-    let previous_env = Box::new(evm.context.env().clone());
-
-    // modify env for pre block call
-    evm_config.fill_tx_env_system_contract_call(
-        &mut evm.context.evm.env,
-        alloy_eips::eip4788::SYSTEM_ADDRESS,
+    let res = transact_system_contract_call(
+        evm_config,
+        evm,
         BEACON_ROOTS_ADDRESS,
         parent_beacon_block_root.0.into(),
-    );
-
-    let mut res = match evm.transact() {
-        Ok(res) => res,
-        Err(e) => {
-            evm.context.evm.env = previous_env;
-            return Err(BlockValidationError::BeaconRootContractCall {
-                parent_beacon_block_root: Box::new(parent_beacon_block_root),
-                message: e.to_string(),
-            }
-            .into())
-        }
-    };
-
-    res.state.remove(&alloy_eips::eip4788::SYSTEM_ADDRESS);
-    res.state.remove(&evm.block().coinbase);
-
-    // re-set the previous env
-    evm.context.evm.env = previous_env;
+    )
+    .map_err(|e| BlockValidationError::BeaconRootContractCall {
+        parent_beacon_block_root: Box::new(parent_beacon_block_root),
+        message: e.to_string(),
+    })?;
 
     Ok(Some(res))
 }
 
 /// Applies the pre-block call to the [EIP-4788] beacon block root contract, using the given block,
-/// chain spec, EVM.
+/// EVM.
 ///
-/// If Cancun is not activated or the block is the genesis block, then this is a no-op, and no
-/// state changes are made.
+/// If Cancun is not enabled in the spec of the EVM or the block is the genesis block, then this is
+/// a no-op, and no state changes are made.
 ///
 /// [EIP-4788]: https://eips.ethereum.org/EIPS/eip-4788
 #[inline]
 pub fn apply_beacon_root_contract_call<EvmConfig, EXT, DB>(
     evm_config: &EvmConfig,
-    chain_spec: impl EthereumHardforks,
-    block_timestamp: u64,
     block_number: u64,
     parent_beacon_block_root: Option<B256>,
     evm: &mut Evm<'_, EXT, DB>,
@@ -147,10 +302,10 @@ where
     DB::Error: core::fmt::Display,
     EvmConfig: ConfigureEvm<Header = Header>,
 {
+    let spec_id = evm.spec_id();
     if let Some(res) = transact_beacon_root_contract_call(
         evm_config,
-        &chain_spec,
-        block_timestamp,
+        spec_id,
         block_number,
         parent_beacon_block_root,
         evm,
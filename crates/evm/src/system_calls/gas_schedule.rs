@@ -0,0 +1,127 @@
+//! Gas schedule overrides for system calls.
+
+use alloc::{boxed::Box, collections::BTreeMap};
+use revm::{
+    handler::register::{EvmHandler, HandleRegisterBox},
+    interpreter::{opcode, InstructionResult},
+    Database,
+};
+
+/// Overrides of the gas costs of individual opcodes, used by chains with non-standard gas rules.
+///
+/// The overridden cost replaces the whole gas charged by the opcode for the configured spec, both
+/// the static and the dynamic part, e.g. the cold access cost of `SLOAD`. Gas refunds of the
+/// opcode are not affected.
+///
+/// Opcodes without an override are charged according to the configured spec.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasScheduleOverride {
+    /// Gas costs by opcode.
+    costs: BTreeMap<u8, u64>,
+}
+
+impl GasScheduleOverride {
+    /// Creates an empty override that keeps the gas costs of the configured spec.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the gas cost of the given opcode.
+    ///
+    /// Only opcodes that continue the execution of the current frame can be overridden, see
+    /// [`Self::is_supported_opcode`].
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, if the opcode isn't supported. In release builds, the override of an
+    /// unsupported opcode is ignored.
+    pub fn with_opcode_cost(mut self, opcode: u8, cost: u64) -> Self {
+        debug_assert!(
+            Self::is_supported_opcode(opcode),
+            "gas cost of opcode {opcode:#04x} can't be overridden"
+        );
+        if Self::is_supported_opcode(opcode) {
+            self.costs.insert(opcode, cost);
+        }
+        self
+    }
+
+    /// Returns `true` if the gas cost of the given opcode can be overridden.
+    ///
+    /// Opcodes that start a new frame (`CALL`, `CALLCODE`, `DELEGATECALL`, `STATICCALL`, `CREATE`,
+    /// `CREATE2` and their EOF counterparts) are not supported, because their gas is settled once
+    /// the new frame returns. Neither are the opcodes that end the current frame (`STOP`,
+    /// `RETURN`, `REVERT`, `INVALID`, `SELFDESTRUCT` and `RETURNCONTRACT`).
+    pub const fn is_supported_opcode(opcode: u8) -> bool {
+        !matches!(
+            opcode,
+            opcode::CALL |
+                opcode::CALLCODE |
+                opcode::DELEGATECALL |
+                opcode::STATICCALL |
+                opcode::CREATE |
+                opcode::CREATE2 |
+                opcode::EXTCALL |
+                opcode::EXTDELEGATECALL |
+                opcode::EXTSTATICCALL |
+                opcode::EOFCREATE |
+                opcode::STOP |
+                opcode::RETURN |
+                opcode::REVERT |
+                opcode::INVALID |
+                opcode::SELFDESTRUCT |
+                opcode::RETURNCONTRACT
+        )
+    }
+
+    /// Returns the overridden gas cost of the given opcode, if any.
+    pub fn opcode_cost(&self, opcode: u8) -> Option<u64> {
+        self.costs.get(&opcode).copied()
+    }
+
+    /// Returns `true` if no opcode cost is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+
+    /// Applies the overrides to the instruction table of the given handler.
+    ///
+    /// Every overridden instruction first checks that the remaining gas covers the overridden
+    /// cost, and fails with [`InstructionResult::OutOfGas`] otherwise. Then it's executed as
+    /// usual, and the gas it charged is replaced by the overridden cost. The gas is left untouched
+    /// if the instruction failed.
+    ///
+    /// Note that the instruction is still charged natively before its gas is replaced, so an
+    /// override cheaper than the native cost fails with [`InstructionResult::OutOfGas`] if the
+    /// remaining gas covers the override, but not the native cost.
+    pub fn apply<EXT, DB: Database>(&self, handler: &mut EvmHandler<'_, EXT, DB>) {
+        for (&opcode, &cost) in &self.costs {
+            handler.instruction_table.update_boxed(
+                opcode,
+                move |instruction, interpreter, host| {
+                    let remaining = interpreter.gas.remaining();
+                    if remaining < cost {
+                        interpreter.instruction_result = InstructionResult::OutOfGas;
+                        return
+                    }
+
+                    instruction(interpreter, host);
+                    if interpreter.instruction_result != InstructionResult::Continue {
+                        return
+                    }
+
+                    interpreter.gas.erase_cost(remaining - interpreter.gas.remaining());
+                    if !interpreter.gas.record_cost(cost) {
+                        interpreter.instruction_result = InstructionResult::OutOfGas;
+                    }
+                },
+            );
+        }
+    }
+
+    /// Returns a handler register that applies the overrides, see [`Self::apply`].
+    pub fn handler_register<'a, EXT, DB: Database>(&self) -> HandleRegisterBox<'a, EXT, DB> {
+        let schedule = self.clone();
+        Box::new(move |handler| schedule.apply(handler))
+    }
+}
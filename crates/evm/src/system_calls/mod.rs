@@ -1,5 +1,14 @@
 //! System contract call functions.
 
+use crate::ConfigureEvm;
+use alloc::{boxed::Box, vec::Vec};
+use alloy_primitives::{Address, Bytes};
+use reth_chainspec::EthereumHardforks;
+use reth_execution_errors::BlockExecutionError;
+use reth_primitives::{Header, Request};
+use revm::{interpreter::Host, Database, DatabaseCommit, Evm};
+use revm_primitives::{EVMError, ResultAndState};
+
 mod eip2935;
 pub use eip2935::*;
 
@@ -11,3 +20,135 @@ pub use eip7002::*;
 
 mod eip7251;
 pub use eip7251::*;
+
+mod gas_schedule;
+pub use gas_schedule::*;
+
+/// Transacts a system call to the contract at the given address with the given input.
+///
+/// The call is executed from the [`SYSTEM_ADDRESS`](alloy_eips::eip4788::SYSTEM_ADDRESS) with the
+/// transaction environment filled by [`ConfigureEvm::fill_tx_env_system_contract_call`]. The
+/// system address and the block beneficiary are removed from the resulting state, and the
+/// previous environment of the EVM is restored afterwards, even if the call failed.
+///
+/// Note: this does not commit the state changes to the database, it only transact the call.
+pub fn transact_system_contract_call<EvmConfig, EXT, DB>(
+    evm_config: &EvmConfig,
+    evm: &mut Evm<'_, EXT, DB>,
+    to: Address,
+    input: Bytes,
+) -> Result<ResultAndState, EVMError<DB::Error>>
+where
+    DB: Database,
+    EvmConfig: ConfigureEvm<Header = Header>,
+{
+    // get previous env
+    let previous_env = Box::new(evm.context.env().clone());
+
+    // modify env for the system call
+    evm_config.fill_tx_env_system_contract_call(
+        &mut evm.context.evm.env,
+        alloy_eips::eip4788::SYSTEM_ADDRESS,
+        to,
+        input,
+    );
+
+    let res = evm.transact().map(|mut res| {
+        res.state.remove(&alloy_eips::eip4788::SYSTEM_ADDRESS);
+        res.state.remove(&evm.block().coinbase);
+        res
+    });
+
+    // re-set the previous env
+    evm.context.evm.env = previous_env;
+
+    res
+}
+
+/// Applies the system calls of a block, depending on the active forks.
+///
+/// The pre-execution changes are:
+///  - the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) beacon root contract call, if Cancun
+///    is active
+///  - the [EIP-2935](https://eips.ethereum.org/EIPS/eip-2935) blockhashes contract call, if Prague
+///    is active
+///
+/// The post-execution changes are:
+///  - the [EIP-7002](https://eips.ethereum.org/EIPS/eip-7002) withdrawal requests contract call, if
+///    Prague is active
+///  - the [EIP-7251](https://eips.ethereum.org/EIPS/eip-7251) consolidation requests contract call,
+///    if Prague is active
+#[derive(Debug, Clone)]
+pub struct SystemCaller<'a, EvmConfig, Chainspec> {
+    evm_config: &'a EvmConfig,
+    chain_spec: Chainspec,
+}
+
+impl<'a, EvmConfig, Chainspec> SystemCaller<'a, EvmConfig, Chainspec> {
+    /// Creates a new system caller with the given EVM config and chain spec.
+    pub const fn new(evm_config: &'a EvmConfig, chain_spec: Chainspec) -> Self {
+        Self { evm_config, chain_spec }
+    }
+}
+
+impl<EvmConfig, Chainspec> SystemCaller<'_, EvmConfig, Chainspec>
+where
+    EvmConfig: ConfigureEvm<Header = Header>,
+    Chainspec: EthereumHardforks,
+{
+    /// Applies the pre-execution system calls for the given block header and commits their state
+    /// changes.
+    pub fn apply_pre_execution_changes<EXT, DB>(
+        &self,
+        header: &Header,
+        evm: &mut Evm<'_, EXT, DB>,
+    ) -> Result<(), BlockExecutionError>
+    where
+        DB: Database + DatabaseCommit,
+        DB::Error: core::fmt::Display,
+    {
+        apply_beacon_root_contract_call(
+            self.evm_config,
+            header.number,
+            header.parent_beacon_block_root,
+            evm,
+        )?;
+        apply_blockhashes_contract_call(
+            self.evm_config,
+            &self.chain_spec,
+            header.timestamp,
+            header.number,
+            header.parent_hash,
+            evm,
+        )?;
+
+        Ok(())
+    }
+
+    /// Applies the post-execution system calls for the given block header, commits their state
+    /// changes and returns the collected requests.
+    ///
+    /// Returns an empty list if Prague is not active.
+    pub fn apply_post_execution_changes<EXT, DB>(
+        &self,
+        header: &Header,
+        evm: &mut Evm<'_, EXT, DB>,
+    ) -> Result<Vec<Request>, BlockExecutionError>
+    where
+        DB: Database + DatabaseCommit,
+        DB::Error: core::fmt::Display,
+    {
+        if !self.chain_spec.is_prague_active_at_timestamp(header.timestamp) {
+            return Ok(Vec::new())
+        }
+
+        // Collect all EIP-7002 requests
+        let withdrawal_requests = apply_withdrawal_requests_contract_call(self.evm_config, evm)?;
+
+        // Collect all EIP-7251 requests
+        let consolidation_requests =
+            apply_consolidation_requests_contract_call(self.evm_config, evm)?;
+
+        Ok([withdrawal_requests, consolidation_requests].concat())
+    }
+}
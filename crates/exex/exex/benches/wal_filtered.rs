@@ -0,0 +1,88 @@
+#![allow(missing_docs, unreachable_pub)]
+use alloy_primitives::{Address, Bytes, Log};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use reth_exex::{ExExNotification, Wal};
+use reth_primitives::{Receipt, Receipts};
+use reth_provider::{Chain, ExecutionOutcome};
+use reth_testing_utils::generators::{self, random_block_range, BlockRangeParams};
+use std::{collections::HashSet, sync::Arc};
+
+/// Compares reading the notifications touching a single address through the bloom-filtered
+/// stream with reading and filtering all notifications in the WAL.
+pub fn stream_filtered(c: &mut Criterion) {
+    let mut group = c.benchmark_group("WAL Stream Filtered");
+    group.sample_size(20);
+
+    for size in [100, 1_000] {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut wal = Wal::new(&temp_dir).unwrap();
+        for notification in generate_notifications(size) {
+            wal.commit(&notification).unwrap();
+        }
+
+        // Only a single notification touches the address
+        let addresses = HashSet::from([address(size / 2)]);
+
+        group.bench_function(BenchmarkId::new("stream filtered", size), |b| {
+            b.iter(|| {
+                let notifications = wal.stream_filtered(&addresses).unwrap().count();
+                assert_eq!(notifications, 1);
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("iter all and filter", size), |b| {
+            b.iter(|| {
+                let notifications = wal
+                    .iter_notifications()
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .filter(|notification| {
+                        notification.committed_chain().is_some_and(|chain| {
+                            chain.touched_addresses().any(|address| addresses.contains(&address))
+                        })
+                    })
+                    .count();
+                assert_eq!(notifications, 1);
+            })
+        });
+    }
+}
+
+/// Generates a committed notification per block, with a single receipt containing a log emitted
+/// by an address unique to the notification.
+fn generate_notifications(size: usize) -> Vec<ExExNotification> {
+    let mut rng = generators::rng();
+
+    random_block_range(
+        &mut rng,
+        0..=size as u64 - 1,
+        BlockRangeParams { tx_count: 0..1, ..Default::default() },
+    )
+    .into_iter()
+    .enumerate()
+    .map(|(i, block)| {
+        let block = block.seal_with_senders().unwrap();
+        let execution_outcome = ExecutionOutcome::new(
+            Default::default(),
+            Receipts {
+                receipt_vec: vec![vec![Some(Receipt {
+                    logs: vec![Log::new_unchecked(address(i), vec![], Bytes::new())],
+                    ..Default::default()
+                })]],
+            },
+            block.number,
+            Vec::new(),
+        );
+        ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], execution_outcome, None)),
+        }
+    })
+    .collect()
+}
+
+fn address(i: usize) -> Address {
+    Address::left_padding_from(&(i as u64 + 1).to_be_bytes())
+}
+
+criterion_group!(wal_filtered, stream_filtered);
+criterion_main!(wal_filtered);
@@ -592,6 +592,7 @@ impl Clone for ExExManagerHandle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wal::WalResult;
     use alloy_primitives::B256;
     use eyre::OptionExt;
     use futures::StreamExt;
@@ -778,7 +779,7 @@ mod tests {
             provider_factory,
             vec![exex_handle],
             10,
-            Wal::new(temp_dir.path()).unwrap(),
+            wal,
             empty_finalized_header_stream(),
         );
 
@@ -829,7 +830,7 @@ mod tests {
             provider_factory,
             vec![exex_handle1, exex_handle2],
             10,
-            Wal::new(temp_dir.path()).unwrap(),
+            wal,
             empty_finalized_header_stream(),
         );
 
@@ -879,7 +880,7 @@ mod tests {
             provider_factory,
             vec![exex_handle1, exex_handle2],
             10,
-            Wal::new(temp_dir.path()).unwrap(),
+            wal,
             empty_finalized_header_stream(),
         );
 
@@ -922,7 +923,7 @@ mod tests {
             provider_factory,
             vec![exex_handle_1],
             max_capacity,
-            Wal::new(temp_dir.path()).unwrap(),
+            wal,
             empty_finalized_header_stream(),
         );
 
@@ -1153,7 +1154,7 @@ mod tests {
 
         assert!(exex_manager.as_mut().poll(&mut cx).is_pending());
         assert_eq!(
-            exex_manager.wal.iter_notifications()?.collect::<eyre::Result<Vec<_>>>()?,
+            exex_manager.wal.iter_notifications()?.collect::<WalResult<Vec<_>>>()?,
             [notification.clone()]
         );
 
@@ -1161,7 +1162,7 @@ mod tests {
         assert!(exex_manager.as_mut().poll(&mut cx).is_pending());
         // WAL isn't finalized because the ExEx didn't emit the `FinishedHeight` event
         assert_eq!(
-            exex_manager.wal.iter_notifications()?.collect::<eyre::Result<Vec<_>>>()?,
+            exex_manager.wal.iter_notifications()?.collect::<WalResult<Vec<_>>>()?,
             [notification.clone()]
         );
 
@@ -1175,7 +1176,7 @@ mod tests {
         // WAL isn't finalized because the ExEx emitted a `FinishedHeight` event with a
         // non-canonical block
         assert_eq!(
-            exex_manager.wal.iter_notifications()?.collect::<eyre::Result<Vec<_>>>()?,
+            exex_manager.wal.iter_notifications()?.collect::<WalResult<Vec<_>>>()?,
             [notification]
         );
 
@@ -1,10 +1,16 @@
-use std::collections::{BTreeMap, VecDeque};
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    ops::RangeInclusive,
+};
 
 use alloy_eips::BlockNumHash;
-use alloy_primitives::B256;
+use alloy_primitives::{Address, BlockNumber, Bloom, BloomInput, B256};
 use dashmap::DashMap;
 use parking_lot::RwLock;
 use reth_exex_types::ExExNotification;
+use serde::{Deserialize, Serialize};
+
+use super::{FileFingerprint, WalResult};
 
 /// The block cache of the WAL.
 ///
@@ -23,12 +29,93 @@ pub struct BlockCache {
     /// For each [`ExExNotification::ChainCommitted`] notification, there will be an entry per
     /// block.
     committed_blocks: DashMap<B256, (u64, CachedBlock)>,
+    /// A mapping of `File ID -> Bloom of touched addresses`.
+    ///
+    /// For each notification, the bloom contains the addresses touched by its committed and
+    /// reverted chains. Notifications without an entry are treated as touching any address.
+    touched_addresses: DashMap<u64, Bloom>,
 }
 
 impl BlockCache {
     /// Creates a new instance of [`BlockCache`].
     pub(super) fn new() -> Self {
-        Self { files: RwLock::new(BTreeMap::new()), committed_blocks: DashMap::new() }
+        Self {
+            files: RwLock::new(BTreeMap::new()),
+            committed_blocks: DashMap::new(),
+            touched_addresses: DashMap::new(),
+        }
+    }
+
+    /// Creates a new instance of [`BlockCache`] from the persisted index.
+    pub(super) fn from_index(index: BlockCacheIndex) -> Self {
+        let cache = Self::new();
+        {
+            let mut files = cache.files.write();
+            for IndexedFile { file_id, blocks, touched_addresses, .. } in index.files {
+                for block in blocks.iter().filter(|block| block.action.is_commit()) {
+                    cache.committed_blocks.insert(block.block.hash, (file_id, *block));
+                }
+                if let Some(touched_addresses) = touched_addresses {
+                    cache.touched_addresses.insert(file_id, touched_addresses);
+                }
+                files.insert(file_id, blocks);
+            }
+        }
+        cache
+    }
+
+    /// Returns the index of the cache that can be persisted and loaded back with
+    /// [`BlockCache::from_index`], with the fingerprint of each notification returned by the
+    /// given closure.
+    pub(super) fn to_index(
+        &self,
+        fingerprint: impl Fn(u64) -> WalResult<Option<FileFingerprint>>,
+    ) -> WalResult<BlockCacheIndex> {
+        let files = self
+            .files
+            .read()
+            .iter()
+            .map(|(file_id, blocks)| (*file_id, blocks.clone()))
+            .collect::<Vec<_>>();
+
+        let files = files
+            .into_iter()
+            .map(|(file_id, blocks)| self.indexed_file(file_id, blocks, &fingerprint))
+            .collect::<WalResult<_>>()?;
+        Ok(BlockCacheIndex { files })
+    }
+
+    /// Returns the update of the index returned by [`BlockCache::to_index`] that inserts the
+    /// notification with the given file ID, or `None` if there's no such notification in the
+    /// cache.
+    pub(super) fn to_index_insert(
+        &self,
+        file_id: u64,
+        fingerprint: impl Fn(u64) -> WalResult<Option<FileFingerprint>>,
+    ) -> WalResult<Option<BlockCacheIndexUpdate>> {
+        let Some(blocks) = self.files.read().get(&file_id).cloned() else { return Ok(None) };
+        Ok(Some(BlockCacheIndexUpdate::Insert(Box::new(self.indexed_file(
+            file_id,
+            blocks,
+            &fingerprint,
+        )?))))
+    }
+
+    fn indexed_file(
+        &self,
+        file_id: u64,
+        blocks: VecDeque<CachedBlock>,
+        fingerprint: impl Fn(u64) -> WalResult<Option<FileFingerprint>>,
+    ) -> WalResult<IndexedFile> {
+        Ok(IndexedFile {
+            file_id,
+            fingerprint: fingerprint(file_id)?,
+            blocks,
+            touched_addresses: self
+                .touched_addresses
+                .get(&file_id)
+                .map(|touched_addresses| *touched_addresses),
+        })
     }
 
     /// Returns `true` if the cache is empty.
@@ -36,6 +123,16 @@ impl BlockCache {
         self.files.read().is_empty()
     }
 
+    /// Returns the number of notifications in the cache.
+    pub(super) fn notifications_count(&self) -> usize {
+        self.files.read().len()
+    }
+
+    /// Returns the file IDs of the notifications in the cache, in ascending order.
+    pub(super) fn file_ids(&self) -> Vec<u64> {
+        self.files.read().keys().copied().collect()
+    }
+
     /// Returns a front-to-back iterator.
     pub(super) fn iter(&self) -> impl Iterator<Item = (u64, CachedBlock)> + '_ {
         self.files
@@ -58,9 +155,60 @@ impl BlockCache {
         self.files.read().last_key_value().and_then(|(k, v)| v.back().map(|b| (*k, *b)))
     }
 
-    /// Removes the notification with the given file ID.
-    pub(super) fn remove_notification(&self, key: u64) -> Option<VecDeque<CachedBlock>> {
-        self.files.write().remove(&key)
+    /// Returns the last committed block that was not reverted by a later notification, or `None`
+    /// if there's no such block in the cache.
+    ///
+    /// Walks the cache backward. A reverted block invalidates all earlier committed blocks with
+    /// the same or higher number, because they're no longer on the canonical chain.
+    pub(super) fn last_committed_block(&self) -> Option<BlockNumHash> {
+        let mut lowest_reverted = BlockNumber::MAX;
+        self.files.read().values().rev().flat_map(|blocks| blocks.iter().rev()).find_map(|block| {
+            match block.action {
+                CachedBlockAction::Commit if block.block.number < lowest_reverted => {
+                    Some(block.block)
+                }
+                CachedBlockAction::Commit => None,
+                CachedBlockAction::Revert => {
+                    lowest_reverted = lowest_reverted.min(block.block.number);
+                    None
+                }
+            }
+        })
+    }
+
+    /// Removes all blocks from the cache.
+    pub(super) fn clear(&self) {
+        self.files.write().clear();
+        self.committed_blocks.clear();
+        self.touched_addresses.clear();
+    }
+
+    /// Renumbers the notifications in the cache with consecutive file IDs starting at `0`,
+    /// keeping their order.
+    pub(super) fn compact_file_ids(&self) {
+        let mut files = self.files.write();
+
+        // The new file ID is never higher than the old one, so renumbering the notifications in
+        // ascending order never overwrites a notification that wasn't renumbered yet.
+        for (new_file_id, (file_id, blocks)) in (0..).zip(std::mem::take(&mut *files)) {
+            for block in blocks.iter().filter(|block| block.action.is_commit()) {
+                self.committed_blocks.insert(block.block.hash, (new_file_id, *block));
+            }
+            if let Some((_, touched_addresses)) = self.touched_addresses.remove(&file_id) {
+                self.touched_addresses.insert(new_file_id, touched_addresses);
+            }
+            files.insert(new_file_id, blocks);
+        }
+    }
+
+    /// Removes the notification with the given file ID, along with its committed blocks.
+    pub(super) fn remove_notification_blocks(&self, file_id: u64) {
+        let Some(blocks) = self.files.write().remove(&file_id) else { return };
+        self.touched_addresses.remove(&file_id);
+        for block in blocks.iter().filter(|block| block.action.is_commit()) {
+            self.committed_blocks
+                .remove_if(&block.block.hash, |_, (block_file_id, _)| *block_file_id == file_id);
+        }
     }
 
     /// Pops the first block from the cache. If it resulted in the whole file entry being empty,
@@ -74,6 +222,7 @@ impl BlockCache {
         let first_block = blocks.pop_front().unwrap();
         if blocks.is_empty() {
             files.remove(&key);
+            self.touched_addresses.remove(&key);
         }
 
         Some((key, first_block))
@@ -90,6 +239,7 @@ impl BlockCache {
         let last_block = blocks.pop_back().unwrap();
         if blocks.is_empty() {
             files.remove(&key);
+            self.touched_addresses.remove(&key);
         }
 
         Some((key, last_block))
@@ -101,44 +251,188 @@ impl BlockCache {
         self.committed_blocks.get(block_hash).map(|entry| entry.0)
     }
 
+    /// Returns the file IDs of the notifications containing at least one block (committed or
+    /// reverted) with a number in the given range, in ascending order.
+    pub(super) fn get_file_ids_by_block_range(
+        &self,
+        range: &RangeInclusive<BlockNumber>,
+    ) -> Vec<u64> {
+        self.files
+            .read()
+            .iter()
+            .filter(|(_, blocks)| blocks.iter().any(|block| range.contains(&block.block.number)))
+            .map(|(file_id, _)| *file_id)
+            .collect()
+    }
+
+    /// Returns the file IDs of the notifications that may touch at least one of the given
+    /// addresses, in ascending order.
+    ///
+    /// The notifications are matched by the bloom of their touched addresses, so the result may
+    /// contain false positives, but never misses a notification touching one of the addresses.
+    pub(super) fn get_file_ids_by_touched_addresses(
+        &self,
+        addresses: &HashSet<Address>,
+    ) -> Vec<u64> {
+        self.files
+            .read()
+            .keys()
+            .filter(|file_id| {
+                self.touched_addresses.get(file_id).map_or(true, |bloom| {
+                    addresses
+                        .iter()
+                        .any(|address| bloom.contains_input(BloomInput::Raw(address.as_slice())))
+                })
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns `true` if the cache contains the notification with the given file ID.
+    pub(super) fn contains_notification(&self, file_id: u64) -> bool {
+        self.files.read().contains_key(&file_id)
+    }
+
+    /// Replaces the blocks of the notification with the given file ID with the blocks from the
+    /// given notification.
+    pub(super) fn replace_notification_blocks_with_file_id(
+        &self,
+        file_id: u64,
+        notification: &ExExNotification,
+    ) {
+        self.remove_notification_blocks(file_id);
+        self.insert_notification_blocks_with_file_id(file_id, notification);
+    }
+
+    /// Returns `true` if the last notification in the cache has the same blocks as the given
+    /// notification.
+    pub(super) fn is_last_notification(&self, notification: &ExExNotification) -> bool {
+        self.files
+            .read()
+            .last_key_value()
+            .is_some_and(|(_, blocks)| blocks.iter().copied().eq(notification_blocks(notification)))
+    }
+
     /// Inserts the blocks from the notification into the cache with the given file ID.
     ///
     /// First, inserts the reverted blocks (if any), then the committed blocks (if any).
+    /// Notifications without blocks aren't tracked by the cache, so no bloom is inserted for them.
     pub(super) fn insert_notification_blocks_with_file_id(
         &self,
         file_id: u64,
         notification: &ExExNotification,
     ) {
+        let blocks = notification_blocks(notification);
+        if blocks.is_empty() {
+            return
+        }
+
         let mut files = self.files.write();
 
-        let reverted_chain = notification.reverted_chain();
-        let committed_chain = notification.committed_chain();
+        let mut touched_addresses = Bloom::default();
+        for chain in notification.reverted_chain().into_iter().chain(notification.committed_chain())
+        {
+            for address in chain.touched_addresses() {
+                touched_addresses.accrue(BloomInput::Raw(address.as_slice()));
+            }
+        }
+        self.touched_addresses.insert(file_id, touched_addresses);
 
-        if let Some(reverted_chain) = reverted_chain {
-            for block in reverted_chain.blocks().values() {
-                files.entry(file_id).or_default().push_back(CachedBlock {
-                    action: CachedBlockAction::Revert,
-                    block: (block.number, block.hash()).into(),
-                    parent_hash: block.parent_hash,
-                });
+        for cached_block in blocks {
+            files.entry(file_id).or_default().push_back(cached_block);
+            if cached_block.action.is_commit() {
+                self.committed_blocks.insert(cached_block.block.hash, (file_id, cached_block));
             }
         }
+    }
+}
+
+/// Returns the blocks of the notification as they're stored in the [`BlockCache`]: first the
+/// reverted blocks (if any), then the committed blocks (if any).
+fn notification_blocks(notification: &ExExNotification) -> Vec<CachedBlock> {
+    let mut blocks = Vec::new();
+
+    if let Some(reverted_chain) = notification.reverted_chain() {
+        blocks.extend(reverted_chain.blocks().values().map(|block| CachedBlock {
+            action: CachedBlockAction::Revert,
+            block: (block.number, block.hash()).into(),
+            parent_hash: block.parent_hash,
+        }));
+    }
+
+    if let Some(committed_chain) = notification.committed_chain() {
+        blocks.extend(committed_chain.blocks().values().map(|block| CachedBlock {
+            action: CachedBlockAction::Commit,
+            block: (block.number, block.hash()).into(),
+            parent_hash: block.parent_hash,
+        }));
+    }
+
+    blocks
+}
+
+/// The persisted snapshot of the [`BlockCache`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct BlockCacheIndex {
+    /// Notifications in the ascending order of file IDs.
+    files: Vec<IndexedFile>,
+}
+
+impl BlockCacheIndex {
+    /// Returns the file IDs of the notifications in the index, in ascending order.
+    pub(super) fn file_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.files.iter().map(|file| file.file_id)
+    }
 
-        if let Some(committed_chain) = committed_chain {
-            for block in committed_chain.blocks().values() {
-                let cached_block = CachedBlock {
-                    action: CachedBlockAction::Commit,
-                    block: (block.number, block.hash()).into(),
-                    parent_hash: block.parent_hash,
-                };
-                files.entry(file_id).or_default().push_back(cached_block);
-                self.committed_blocks.insert(block.hash(), (file_id, cached_block));
+    /// Returns the file IDs of the notifications in the index with their fingerprints at the time
+    /// the index was created.
+    pub(super) fn fingerprints(&self) -> impl Iterator<Item = (u64, Option<FileFingerprint>)> + '_ {
+        self.files.iter().map(|file| (file.file_id, file.fingerprint))
+    }
+
+    /// Applies the update to the index.
+    ///
+    /// Each update replaces the state of the notifications it refers to, so applying the updates
+    /// to an index that already includes them doesn't change it.
+    pub(super) fn apply(&mut self, update: BlockCacheIndexUpdate) {
+        match update {
+            BlockCacheIndexUpdate::Insert(file) => {
+                match self.files.binary_search_by_key(&file.file_id, |file| file.file_id) {
+                    Ok(index) => self.files[index] = *file,
+                    Err(index) => self.files.insert(index, *file),
+                }
+            }
+            BlockCacheIndexUpdate::Remove(file_ids) => {
+                self.files.retain(|file| !file_ids.contains(&file.file_id))
             }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An update of the [`BlockCacheIndex`], appended to the persisted index on every change of the
+/// [`BlockCache`] until the index is persisted again.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) enum BlockCacheIndexUpdate {
+    /// The notification was inserted or replaced.
+    Insert(Box<IndexedFile>),
+    /// The notifications with the given file IDs were removed.
+    Remove(RangeInclusive<u64>),
+}
+
+/// A notification in the [`BlockCacheIndex`].
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct IndexedFile {
+    file_id: u64,
+    /// See [`Storage::file_fingerprint`](super::Storage::file_fingerprint).
+    fingerprint: Option<FileFingerprint>,
+    blocks: VecDeque<CachedBlock>,
+    /// Bloom of the addresses touched by the notification, `None` if the index was created before
+    /// the addresses were indexed.
+    #[serde(default)]
+    touched_addresses: Option<Bloom>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) struct CachedBlock {
     pub(super) action: CachedBlockAction,
     /// The block number and hash of the block.
@@ -147,7 +441,7 @@ pub(super) struct CachedBlock {
     pub(super) parent_hash: B256,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum CachedBlockAction {
     Commit,
     Revert,
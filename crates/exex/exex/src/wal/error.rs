@@ -0,0 +1,68 @@
+//! Wal Errors
+
+use std::{ops::RangeInclusive, path::PathBuf};
+
+use reth_fs_util::FsPathError;
+
+/// Wal Result type.
+pub type WalResult<T> = Result<T, WalError>;
+
+/// Wal Error types
+#[derive(Debug, thiserror::Error)]
+pub enum WalError {
+    /// I/O error.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Filesystem error.
+    #[error(transparent)]
+    FsPathError(#[from] FsPathError),
+    /// Failed to encode a notification or an export.
+    #[error("failed to encode: {0}")]
+    Encode(#[source] serde_json::Error),
+    /// Failed to decode an export.
+    #[error("failed to decode: {0}")]
+    Decode(#[source] serde_json::Error),
+    /// The notification file with the given file ID can't be decoded.
+    #[error("notification {file_id} is corrupted: {source}")]
+    Corrupt {
+        /// File ID of the notification.
+        file_id: u64,
+        /// The decoding error.
+        #[source]
+        source: serde_json::Error,
+    },
+    /// The notification with the given file ID doesn't exist.
+    #[error("notification {file_id} not found")]
+    NotFound {
+        /// File ID of the notification.
+        file_id: u64,
+    },
+    /// The notification file or the export was written with an unsupported version of the format.
+    #[error("unsupported WAL version {0}")]
+    VersionUnsupported(u64),
+    /// The file in the WAL directory is not a notification file.
+    #[error("failed to parse file name: {0}")]
+    InvalidFileName(String),
+    /// The WAL directory is already opened by another instance.
+    #[error("WAL directory {0:?} is already opened by another instance")]
+    DirectoryLocked(PathBuf),
+    /// The WAL was opened in read-only mode.
+    #[error("WAL storage at {0:?} is opened in read-only mode")]
+    ReadOnly(PathBuf),
+    /// The WAL storage already contains notifications with the given file IDs.
+    #[error("WAL storage is not empty, found notifications with file IDs {0:?}")]
+    StorageNotEmpty(RangeInclusive<u64>),
+    /// The committed notification has the same blocks as the last notification in the WAL.
+    #[error("notification is a duplicate of notification {file_id}")]
+    DuplicateNotification {
+        /// File ID of the last notification.
+        file_id: u64,
+    },
+    /// The WAL export is malformed.
+    #[error("invalid WAL export: {0}")]
+    InvalidExport(String),
+    /// The task committing the notifications queued with
+    /// [`Wal::commit_async`](super::Wal::commit_async) is closed.
+    #[error("WAL commit task is closed")]
+    CommitTaskClosed,
+}
@@ -2,22 +2,45 @@
 
 mod cache;
 pub use cache::BlockCache;
+use cache::{BlockCacheIndex, BlockCacheIndexUpdate};
+mod error;
+pub use error::{WalError, WalResult};
 mod storage;
-use eyre::OptionExt;
-pub use storage::Storage;
+pub use storage::{FileFingerprint, FileStorage, InMemoryStorage, Storage, WalLayout};
 
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::HashSet,
+    future::Future,
+    io::{BufRead, BufReader, Read, Write},
+    ops::RangeInclusive,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use alloy_eips::BlockNumHash;
-use alloy_primitives::B256;
+use alloy_primitives::{Address, BlockNumber, B256};
+use parking_lot::Mutex;
 use reth_exex_types::ExExNotification;
-use reth_tracing::tracing::{debug, instrument};
+use reth_provider::{ChainSplit, ChainSplitTarget};
+use reth_tracing::tracing::{debug, instrument, warn};
+use tokio::sync::{mpsc, oneshot};
+
+/// Version of the format produced by [`Wal::export`].
+const WAL_EXPORT_VERSION: u64 = 1;
+
+/// Maximum number of notifications queued by [`Wal::commit_async`] that are not committed yet.
+const ASYNC_COMMIT_QUEUE_CAPACITY: usize = 16;
 
 /// WAL is a write-ahead log (WAL) that stores the notifications sent to ExExes.
 ///
-/// WAL is backed by a directory of binary files represented by [`Storage`] and a block cache
-/// represented by [`BlockCache`]. The role of the block cache is to avoid walking the WAL directory
-/// and decoding notifications every time we want to iterate or finalize the WAL.
+/// WAL is backed by a [`Storage`] (by default, a directory of binary files represented by
+/// [`FileStorage`]) and a block cache represented by [`BlockCache`]. The role of the block cache is
+/// to avoid walking the WAL directory and decoding notifications every time we want to iterate or
+/// finalize the WAL.
 ///
 /// The expected mode of operation is as follows:
 /// 1. On every new canonical chain notification, call [`Wal::commit`].
@@ -26,12 +49,107 @@ use reth_tracing::tracing::{debug, instrument};
 #[derive(Debug, Clone)]
 pub struct Wal {
     inner: Arc<WalInner>,
+    /// Durability guarantees of [`Wal::commit`].
+    durability: WalDurability,
+    /// Handling of duplicate notifications in [`Wal::commit`].
+    deduplication: WalDeduplication,
+    /// Whether [`Wal::finalize`] splits the notification containing the finalized block.
+    split_on_finalize: bool,
+    /// The queue of notifications committed with [`Wal::commit_async`]. Initialized on the first
+    /// call.
+    async_commits: Arc<Mutex<Option<mpsc::Sender<AsyncCommit>>>>,
 }
 
 impl Wal {
-    /// Creates a new instance of [`Wal`].
-    pub fn new(directory: impl AsRef<Path>) -> eyre::Result<Self> {
-        Ok(Self { inner: Arc::new(WalInner::new(directory)?) })
+    /// Creates a new instance of [`Wal`] backed by the given directory.
+    ///
+    /// The directory and its parents are created if they don't exist. If the directory contains
+    /// no notifications, the WAL is returned empty without reading anything else from it.
+    ///
+    /// Returns an error if the directory is already opened by another instance.
+    pub fn new(directory: impl AsRef<Path>) -> WalResult<Self> {
+        Ok(Self::from_inner(WalInner::new(Box::new(FileStorage::new(directory)?))?))
+    }
+
+    /// Same as [`Wal::new`], but with the given layout of the notification files in the
+    /// directory. The directory must always be opened with the same layout.
+    pub fn new_with_layout(directory: impl AsRef<Path>, layout: WalLayout) -> WalResult<Self> {
+        Ok(Self::from_inner(WalInner::new(Box::new(FileStorage::new_with_layout(
+            directory, layout,
+        )?))?))
+    }
+
+    /// Opens the WAL in the given directory in read-only mode.
+    ///
+    /// Multiple read-only instances can be opened for the same directory at the same time, but
+    /// not alongside the one created with [`Wal::new`]. Committing to or finalizing a read-only
+    /// WAL returns an error.
+    pub fn open_read_only(directory: impl AsRef<Path>) -> WalResult<Self> {
+        Self::open_read_only_with_layout(directory, WalLayout::default())
+    }
+
+    /// Same as [`Wal::open_read_only`], but with the given layout of the notification files in
+    /// the directory.
+    pub fn open_read_only_with_layout(
+        directory: impl AsRef<Path>,
+        layout: WalLayout,
+    ) -> WalResult<Self> {
+        Ok(Self::from_inner(WalInner::new(Box::new(FileStorage::new_read_only(
+            directory, layout,
+        )?))?))
+    }
+
+    /// Creates a new instance of [`Wal`] backed by [`InMemoryStorage`].
+    ///
+    /// Nothing is written to disk, so the notifications are lost when the WAL is dropped.
+    pub fn new_in_memory() -> Self {
+        Self::from_inner(WalInner::empty(Box::new(InMemoryStorage::new())))
+    }
+
+    /// Creates a new instance of [`Wal`] around the given inner WAL, with the default options.
+    fn from_inner(inner: WalInner) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            durability: WalDurability::default(),
+            deduplication: WalDeduplication::default(),
+            split_on_finalize: false,
+            async_commits: Default::default(),
+        }
+    }
+
+    /// Sets the durability guarantees of [`Wal::commit`]. Defaults to
+    /// [`WalDurability::PerEntry`].
+    pub const fn with_durability(mut self, durability: WalDurability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Sets the handling of duplicate notifications in [`Wal::commit`]. Defaults to
+    /// [`WalDeduplication::Disabled`].
+    pub const fn with_deduplication(mut self, deduplication: WalDeduplication) -> Self {
+        self.deduplication = deduplication;
+        self
+    }
+
+    /// Imports the WAL previously exported with [`Wal::export`] into the given directory, and
+    /// creates a new instance of [`Wal`] backed by it.
+    ///
+    /// The notifications are written under the same file IDs they had in the exported WAL. The
+    /// directory must not contain any notifications.
+    pub fn import(directory: impl AsRef<Path>, reader: impl Read) -> WalResult<Self> {
+        Ok(Self::from_inner(WalInner::import(Box::new(FileStorage::new(directory)?), reader)?))
+    }
+
+    /// Sets whether [`Wal::finalize`] splits the notification containing the finalized block,
+    /// if it also contains unfinalized blocks. Defaults to `false`.
+    ///
+    /// If enabled, the finalized blocks are removed from such notification, and it's rewritten
+    /// with the remaining unfinalized blocks and their execution outcome under the same file ID.
+    /// Only [`ExExNotification::ChainCommitted`] notifications are split, other notifications are
+    /// preserved whole.
+    pub const fn with_split_on_finalize(mut self, split_on_finalize: bool) -> Self {
+        self.split_on_finalize = split_on_finalize;
+        self
     }
 
     /// Returns a read-only handle to the WAL.
@@ -40,8 +158,114 @@ impl Wal {
     }
 
     /// Commits the notification to WAL.
-    pub fn commit(&mut self, notification: &ExExNotification) -> eyre::Result<()> {
-        self.inner.commit(notification)
+    ///
+    /// If the notification has the same blocks as the last notification in the WAL, it's handled
+    /// according to [`Wal::with_deduplication`].
+    pub fn commit(&mut self, notification: &ExExNotification) -> WalResult<()> {
+        self.inner.commit(notification, self.durability, self.deduplication)
+    }
+
+    /// Commits the notification to WAL on a dedicated thread, without blocking the caller.
+    ///
+    /// The notification is submitted to a bounded queue when the returned future is first polled,
+    /// and the notifications are committed one by one in the order of submission. Awaiting the
+    /// futures in the order of the calls, sequentially or concurrently (e.g. with
+    /// [`futures::future::join_all`]), commits the notifications in the order of the calls.
+    ///
+    /// If the queue is full, the future waits for the storage to catch up before submitting the
+    /// notification, providing backpressure. The future resolves once the notification is
+    /// committed.
+    ///
+    /// NOTE: Calling [`Wal::commit`] while there are notifications in the queue commits its
+    /// notification in between the queued ones.
+    pub fn commit_async(
+        &self,
+        notification: ExExNotification,
+    ) -> impl Future<Output = WalResult<()>> + Send + 'static {
+        let queue = self.async_commit_queue();
+        let (durability, deduplication) = (self.durability, self.deduplication);
+
+        async move {
+            let (tx, rx) = oneshot::channel();
+            queue?
+                .send(AsyncCommit { notification, durability, deduplication, tx })
+                .await
+                .map_err(|_| WalError::CommitTaskClosed)?;
+            rx.await.map_err(|_| WalError::CommitTaskClosed)?
+        }
+    }
+
+    /// Returns the queue of [`Wal::commit_async`], spawning the thread committing the queued
+    /// notifications if it's not running yet.
+    fn async_commit_queue(&self) -> WalResult<mpsc::Sender<AsyncCommit>> {
+        let mut queue = self.async_commits.lock();
+        if let Some(queue) = queue.as_ref() {
+            return Ok(queue.clone())
+        }
+
+        let inner = self.inner.clone();
+        let (tx, mut rx) = mpsc::channel::<AsyncCommit>(ASYNC_COMMIT_QUEUE_CAPACITY);
+        std::thread::Builder::new().name("exex-wal-commit".to_string()).spawn(move || {
+            while let Some(AsyncCommit { notification, durability, deduplication, tx }) =
+                rx.blocking_recv()
+            {
+                let _ = tx.send(inner.commit(&notification, durability, deduplication));
+            }
+        })?;
+
+        Ok(queue.insert(tx).clone())
+    }
+
+    /// Replaces the notification with the given file ID, keeping the file IDs of all other
+    /// notifications unchanged.
+    ///
+    /// Returns an error if there's no notification with the given file ID.
+    pub fn replace_entry(
+        &mut self,
+        file_id: u64,
+        notification: &ExExNotification,
+    ) -> WalResult<()> {
+        self.inner.replace_entry(file_id, notification)
+    }
+
+    /// Removes the notifications from the storage that are not referenced by the block cache,
+    /// e.g. left behind by a crash while finalizing, and returns their file IDs in ascending
+    /// order.
+    ///
+    /// This is a maintenance operation that can be run after opening the WAL. It scans the whole
+    /// storage, so it's not meant to be run on every commit.
+    pub fn gc(&mut self) -> WalResult<Vec<u64>> {
+        self.inner.gc()
+    }
+
+    /// Syncs all notifications committed to the WAL to the disk, and rewrites the block cache
+    /// index with the updates appended to it on every commit and finalization.
+    ///
+    /// Syncing is only needed if the WAL is configured with [`WalDurability::None`] or
+    /// [`WalDurability::Periodic`], as the notifications are otherwise synced on commit. The
+    /// block cache index is kept valid without flushing, and is also rewritten when the last clone
+    /// of the WAL is dropped.
+    ///
+    /// NOTE: Notifications queued by [`Wal::commit_async`] that are not committed yet are not
+    /// synced.
+    pub fn flush(&mut self) -> WalResult<()> {
+        self.inner.flush()
+    }
+
+    /// Flushes the WAL with [`Wal::flush`] and closes it, returning any error that occurred while
+    /// flushing.
+    ///
+    /// Other clones of the WAL and its handles stay usable.
+    pub fn close(mut self) -> WalResult<()> {
+        self.flush()
+    }
+
+    /// Returns all notifications in the WAL in order, and removes them from the WAL.
+    ///
+    /// After the WAL is drained, the next committed notification gets the file ID `0`. If the
+    /// node crashes while draining, the WAL contains either all notifications or none of them.
+    pub fn drain(&mut self) -> WalResult<Vec<ExExNotification>> {
+        self.inner.drain()
     }
 
     /// Finalizes the WAL to the given block, inclusive.
@@ -49,38 +273,369 @@ impl Wal {
     /// 1. Finds a notification with first unfinalized block (first notification containing a
     ///    committed block higher than `to_block`).
     /// 2. Removes the notifications from the beginning of WAL until the found notification. If this
-    ///    notification includes both finalized and non-finalized blocks, it will not be removed.
-    pub fn finalize(&self, to_block: BlockNumHash) -> eyre::Result<()> {
-        self.inner.finalize(to_block)
+    ///    notification includes both finalized and non-finalized blocks, it will not be removed,
+    ///    unless splitting is enabled with [`Wal::with_split_on_finalize`].
+    pub fn finalize(&self, to_block: BlockNumHash) -> WalResult<()> {
+        self.inner.finalize(to_block, self.split_on_finalize)
+    }
+
+    /// Finalizes the WAL to the given block with [`Wal::finalize`], and rewrites the remaining
+    /// notifications with consecutive file IDs starting at `0`, keeping their order.
+    ///
+    /// The notifications are rewritten without decoding them. If the node crashes while
+    /// compacting, the WAL contains either the notifications from before the compaction, or the
+    /// compacted ones.
+    pub fn compact_to(&mut self, to_block: BlockNumHash) -> WalResult<()> {
+        self.inner.finalize(to_block, self.split_on_finalize)?;
+        self.inner.compact()
+    }
+
+    /// Returns the first block in the WAL, or `None` if the WAL is empty.
+    ///
+    /// The block is read from the block cache, without decoding any notifications.
+    pub fn earliest_block(&self) -> Option<BlockNumHash> {
+        self.inner.block_cache.front().map(|(_, cached_block)| cached_block.block)
+    }
+
+    /// Returns the last block in the WAL, or `None` if the WAL is empty.
+    ///
+    /// The block is read from the block cache, without decoding any notifications.
+    ///
+    /// NOTE: This is the last block recorded in the WAL, regardless of whether it was committed or
+    /// reverted. If the last notification is a [`ExExNotification::ChainReverted`], the last
+    /// reverted block is returned.
+    pub fn latest_block(&self) -> Option<BlockNumHash> {
+        self.inner.block_cache.back().map(|(_, cached_block)| cached_block.block)
+    }
+
+    /// Returns the last committed block in the WAL that is still on the canonical chain, or `None`
+    /// if there's no such block.
+    ///
+    /// Unlike [`Wal::latest_block`], reverted blocks are skipped, as well as committed blocks
+    /// that were reverted by a later notification. This is the block to resume an ExEx from.
+    ///
+    /// The block is read from the block cache, without decoding any notifications.
+    pub fn last_committed_block(&self) -> Option<BlockNumHash> {
+        self.inner.block_cache.last_committed_block()
+    }
+
+    /// Returns the number of notification files in the WAL, or `0` if the WAL is empty.
+    ///
+    /// File IDs missing from the middle of the WAL are not counted.
+    pub fn files_count(&self) -> WalResult<usize> {
+        Ok(self.inner.storage.file_ids()?.len())
+    }
+
+    /// Returns the path to the WAL directory, or `None` if the WAL is backed by
+    /// [`InMemoryStorage`].
+    pub fn directory(&self) -> Option<&Path> {
+        self.inner.storage.directory()
     }
 
     /// Returns an iterator over all notifications in the WAL.
     pub fn iter_notifications(
         &self,
-    ) -> eyre::Result<Box<dyn Iterator<Item = eyre::Result<ExExNotification>> + '_>> {
+    ) -> WalResult<Box<dyn Iterator<Item = WalResult<ExExNotification>> + '_>> {
         self.inner.iter_notifications()
     }
+
+    /// Exports all notifications in the WAL to the given writer.
+    ///
+    /// The export starts with a versioned header line, followed by a line per notification with
+    /// its file ID, in the ascending order of file IDs. The output is deterministic for the same
+    /// WAL contents and can be imported back with [`Wal::import`].
+    pub fn export(&self, writer: impl Write) -> WalResult<()> {
+        self.inner.export(writer)
+    }
+
+    /// Returns an iterator over the notifications in the WAL that contain at least one block
+    /// (committed or reverted) in the given block range.
+    ///
+    /// Only the notifications intersecting the range are read from the storage. Notifications
+    /// spanning the range boundaries are returned whole.
+    pub fn iter_range(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> WalResult<impl Iterator<Item = WalResult<ExExNotification>> + '_> {
+        self.inner.iter_range(range)
+    }
+
+    /// Returns an iterator over the notifications in the WAL that touch at least one of the given
+    /// addresses.
+    ///
+    /// A notification matches if either its committed or reverted chain touches one of the
+    /// addresses. See [`Chain::touched_addresses`](reth_provider::Chain::touched_addresses) for
+    /// the definition of a touched address.
+    ///
+    /// The block cache keeps a bloom of the touched addresses for every notification, so only the
+    /// notifications that may match are read from the storage.
+    pub fn stream_filtered<'a>(
+        &'a self,
+        addresses: &'a HashSet<Address>,
+    ) -> WalResult<impl Iterator<Item = WalResult<ExExNotification>> + 'a> {
+        self.inner.stream_filtered(addresses)
+    }
+}
+
+/// Durability guarantees of the notifications committed to the WAL.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WalDurability {
+    /// Notifications are never explicitly synced to the disk, leaving it up to the OS to flush
+    /// them. The fastest option, but notifications may be lost or corrupted on a crash.
+    None,
+    /// Every notification is synced to the disk before [`Wal::commit`] returns.
+    #[default]
+    PerEntry,
+    /// Notifications are synced to the disk on commit if at least the given duration has passed
+    /// since the last sync. Notifications committed in between may be lost on a crash.
+    Periodic(Duration),
+}
+
+/// Handling of a committed notification that has the same blocks as the last notification in the
+/// WAL, e.g. because the same notification was accidentally committed twice.
+///
+/// Notifications are compared by the numbers and hashes of their reverted and committed blocks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WalDeduplication {
+    /// Duplicates are committed as any other notification.
+    #[default]
+    Disabled,
+    /// Duplicates are skipped, and [`Wal::commit`] returns without writing them.
+    Skip,
+    /// Duplicates are rejected with [`WalError::DuplicateNotification`].
+    Reject,
+}
+
+/// A notification queued by [`Wal::commit_async`].
+#[derive(Debug)]
+struct AsyncCommit {
+    notification: ExExNotification,
+    durability: WalDurability,
+    deduplication: WalDeduplication,
+    /// Receives the result of the commit.
+    tx: oneshot::Sender<WalResult<()>>,
 }
 
 /// Inner type for the WAL.
 #[derive(Debug)]
 struct WalInner {
-    /// The underlying WAL storage backed by a file.
-    storage: Storage,
+    /// The underlying WAL storage.
+    storage: Box<dyn Storage>,
     /// WAL block cache. See [`cache::BlockCache`] docs for more details.
     block_cache: BlockCache,
+    /// The time of the last sync of the storage.
+    last_synced_at: Mutex<Instant>,
+    /// Serializes the modifications of the storage and the block cache, so that concurrent
+    /// commits don't write under the same file ID, and finalization, compaction or draining
+    /// doesn't remove or renumber the notifications while they're written.
+    write_lock: Mutex<()>,
+    /// Whether the block cache changed since the block cache index was persisted.
+    ///
+    /// Every change is appended to the persisted index as an update, see
+    /// [`WalInner::append_block_cache_index_update`]. The index is rewritten with the updates
+    /// included on flush and when the WAL is closed, see [`WalInner::write_block_cache_index`].
+    is_block_cache_index_outdated: AtomicBool,
 }
 
 impl WalInner {
-    fn new(directory: impl AsRef<Path>) -> eyre::Result<Self> {
-        let mut wal = Self { storage: Storage::new(directory)?, block_cache: BlockCache::new() };
-        wal.fill_block_cache()?;
+    fn new(storage: Box<dyn Storage>) -> WalResult<Self> {
+        let mut wal = Self::empty(storage);
+        // Nothing to recover or cache in an empty or newly created directory
+        if wal.storage.files_range()?.is_none() {
+            debug!(target: "exex::wal", "WAL is empty");
+            return Ok(wal)
+        }
+
+        wal.recover_truncated_tail()?;
+        if !wal.load_block_cache_index()? {
+            wal.fill_block_cache()?;
+            wal.mark_block_cache_index_outdated();
+            wal.try_write_block_cache_index();
+        }
         Ok(wal)
     }
 
+    /// Creates a new instance with an empty block cache, without reading the storage.
+    fn empty(storage: Box<dyn Storage>) -> Self {
+        Self {
+            storage,
+            block_cache: BlockCache::new(),
+            last_synced_at: Mutex::new(Instant::now()),
+            write_lock: Mutex::new(()),
+            is_block_cache_index_outdated: AtomicBool::new(false),
+        }
+    }
+
+    /// Removes the last notification from the storage if it's truncated, which happens if the
+    /// node crashed before the renamed notification file was persisted.
+    ///
+    /// Only the last notification is checked, and only a truncated one is removed. Notifications
+    /// that fail to decode for any other reason are still reported as errors when read. A
+    /// read-only WAL doesn't remove anything, the notification is recovered by the next
+    /// read-write one.
+    #[instrument(target = "exex::wal", skip(self))]
+    fn recover_truncated_tail(&self) -> WalResult<()> {
+        if self.storage.is_read_only() {
+            return Ok(())
+        }
+        let Some(files_range) = self.storage.files_range()? else { return Ok(()) };
+
+        let file_id = *files_range.end();
+        match self.storage.read_notification(file_id) {
+            Err(WalError::Corrupt { file_id, source }) if source.is_eof() => {
+                warn!(
+                    target: "exex::wal",
+                    file_id,
+                    %source,
+                    "Removing the truncated last notification from WAL"
+                );
+                self.storage.remove_notifications(file_id..=file_id)?;
+                Ok(())
+            }
+            Err(err) => Err(err),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    /// Loads the block cache from the index persisted in the storage, without decoding any
+    /// notifications.
+    ///
+    /// Returns `false` if the index is missing, can't be decoded, or is inconsistent with the
+    /// notifications in the storage, in which case the block cache needs to be filled from the
+    /// notifications.
+    #[instrument(target = "exex::wal", skip(self))]
+    fn load_block_cache_index(&mut self) -> WalResult<bool> {
+        let data = self.storage.read_block_cache_index()?;
+        let updates = self.storage.read_block_cache_index_updates()?;
+        if data.is_none() && updates.is_none() {
+            debug!("Block cache index is missing");
+            return Ok(false)
+        }
+
+        // Without the index, the updates are applied to an empty one, e.g. if the node crashed
+        // before the index of a new WAL was persisted
+        let mut index = match data.map(|data| serde_json::from_slice::<BlockCacheIndex>(&data)) {
+            Some(Ok(index)) => index,
+            Some(Err(err)) => {
+                debug!(%err, "Failed to decode block cache index");
+                return Ok(false)
+            }
+            None => BlockCacheIndex::default(),
+        };
+        for update in updates.iter().flat_map(|updates| updates.split(|byte| *byte == b'\n')) {
+            if update.is_empty() {
+                continue
+            }
+            // The last update is truncated if the node crashed while appending it
+            match serde_json::from_slice::<BlockCacheIndexUpdate>(update) {
+                Ok(update) => index.apply(update),
+                Err(err) => {
+                    debug!(%err, "Failed to decode block cache index update");
+                    return Ok(false)
+                }
+            }
+        }
+
+        let file_ids = self.storage.file_ids()?;
+        if !index.file_ids().eq(file_ids.iter().copied()) {
+            debug!("Block cache index is stale, the notifications were added or removed");
+            return Ok(false)
+        }
+        for (file_id, fingerprint) in index.fingerprints() {
+            if fingerprint.is_none() || fingerprint != self.storage.file_fingerprint(file_id)? {
+                debug!(file_id, "Block cache index is stale, the notification was rewritten");
+                return Ok(false)
+            }
+        }
+
+        self.block_cache = BlockCache::from_index(index);
+        // Fold the updates into the index on the next flush
+        if updates.is_some() {
+            self.mark_block_cache_index_outdated();
+        }
+        debug!(
+            cached_notifications = self.block_cache.notifications_count(),
+            "Loaded block cache from the index"
+        );
+
+        Ok(true)
+    }
+
+    /// Marks the block cache index as outdated, so that it's persisted on the next flush.
+    fn mark_block_cache_index_outdated(&self) {
+        self.is_block_cache_index_outdated.store(true, Ordering::Relaxed);
+    }
+
+    /// Appends the update returned by the closure to the persisted block cache index, so that the
+    /// index stays valid if the node crashes before it's persisted again.
+    ///
+    /// Failing to append the update is not an error, as it only makes the persisted index stale.
+    fn append_block_cache_index_update(
+        &self,
+        update: impl FnOnce() -> WalResult<Option<BlockCacheIndexUpdate>>,
+    ) {
+        self.mark_block_cache_index_outdated();
+
+        let result = update().and_then(|update| {
+            let Some(update) = update else { return Ok(()) };
+            let mut data = serde_json::to_vec(&update).map_err(WalError::Encode)?;
+            data.push(b'\n');
+            self.storage.append_block_cache_index_update(&data)
+        });
+        if let Err(err) = result {
+            warn!(target: "exex::wal", %err, "Failed to append the update to the block cache index");
+        }
+    }
+
+    /// Appends the notification with the given file ID in the block cache to the persisted block
+    /// cache index, see [`WalInner::append_block_cache_index_update`].
+    fn append_block_cache_index_insert(&self, file_id: u64) {
+        self.append_block_cache_index_update(|| {
+            self.block_cache
+                .to_index_insert(file_id, |file_id| self.storage.file_fingerprint(file_id))
+        });
+    }
+
+    /// Appends the removal of the notifications with the given file IDs to the persisted block
+    /// cache index, see [`WalInner::append_block_cache_index_update`].
+    fn append_block_cache_index_remove(&self, file_ids: RangeInclusive<u64>) {
+        self.append_block_cache_index_update(|| Ok(Some(BlockCacheIndexUpdate::Remove(file_ids))));
+    }
+
+    /// Persists the block cache index with [`WalInner::write_block_cache_index`], logging the
+    /// error instead of returning it.
+    fn try_write_block_cache_index(&self) {
+        if let Err(err) = self.write_block_cache_index() {
+            warn!(target: "exex::wal", %err, "Failed to persist the block cache index");
+        }
+    }
+
+    /// Persists the block cache index if the block cache changed since it was last persisted, so
+    /// that the block cache can be loaded on startup without decoding the notifications.
+    ///
+    /// The index is validated against the notifications when it's loaded, so the notifications
+    /// committed after the index was persisted only make it stale.
+    fn write_block_cache_index(&self) -> WalResult<()> {
+        if self.storage.is_read_only() ||
+            !self.is_block_cache_index_outdated.swap(false, Ordering::Relaxed)
+        {
+            return Ok(())
+        }
+
+        let result = self
+            .block_cache
+            .to_index(|file_id| self.storage.file_fingerprint(file_id))
+            .and_then(|index| serde_json::to_vec(&index).map_err(WalError::Encode))
+            .and_then(|data| self.storage.write_block_cache_index(&data));
+        if result.is_err() {
+            self.mark_block_cache_index_outdated();
+        }
+        result
+    }
+
     /// Fills the block cache with the notifications from the storage.
     #[instrument(target = "exex::wal", skip(self))]
-    fn fill_block_cache(&mut self) -> eyre::Result<()> {
+    fn fill_block_cache(&mut self) -> WalResult<()> {
         let Some(files_range) = self.storage.files_range()? else { return Ok(()) };
 
         for entry in self.storage.iter_notifications(files_range) {
@@ -107,22 +662,81 @@ impl WalInner {
         reverted_block_range = ?notification.reverted_chain().as_ref().map(|chain| chain.range()),
         committed_block_range = ?notification.committed_chain().as_ref().map(|chain| chain.range())
     ))]
-    fn commit(&self, notification: &ExExNotification) -> eyre::Result<()> {
+    fn commit(
+        &self,
+        notification: &ExExNotification,
+        durability: WalDurability,
+        deduplication: WalDeduplication,
+    ) -> WalResult<()> {
+        let _write_lock = self.write_lock.lock();
+
+        if deduplication != WalDeduplication::Disabled &&
+            self.block_cache.is_last_notification(notification)
+        {
+            let file_id = self.block_cache.back().map_or(0, |block| block.0);
+            if deduplication == WalDeduplication::Reject {
+                return Err(WalError::DuplicateNotification { file_id })
+            }
+
+            debug!(file_id, "Skipped duplicate notification");
+            return Ok(())
+        }
+
+        let sync = match durability {
+            WalDurability::None => false,
+            WalDurability::PerEntry => true,
+            WalDurability::Periodic(interval) => self.last_synced_at.lock().elapsed() >= interval,
+        };
+
         let file_id = self.block_cache.back().map_or(0, |block| block.0 + 1);
-        self.storage.write_notification(file_id, notification)?;
+        let bytes_written = self.storage.write_notification(file_id, notification, sync)?;
+        if sync {
+            *self.last_synced_at.lock() = Instant::now();
+        }
 
         debug!(?file_id, "Inserting notification blocks into the block cache");
         self.block_cache.insert_notification_blocks_with_file_id(file_id, notification);
+        self.append_block_cache_index_insert(file_id);
+
+        debug!(
+            file_id,
+            bytes_written,
+            cached_notifications = self.block_cache.notifications_count(),
+            "Committed notification to WAL"
+        );
+
+        Ok(())
+    }
+
+    #[instrument(target = "exex::wal", skip(self, notification), fields(
+        reverted_block_range = ?notification.reverted_chain().as_ref().map(|chain| chain.range()),
+        committed_block_range = ?notification.committed_chain().as_ref().map(|chain| chain.range())
+    ))]
+    fn replace_entry(&self, file_id: u64, notification: &ExExNotification) -> WalResult<()> {
+        let _write_lock = self.write_lock.lock();
+
+        if !self.block_cache.contains_notification(file_id) {
+            return Err(WalError::NotFound { file_id })
+        }
+
+        let bytes_written = self.storage.write_notification(file_id, notification, true)?;
+        self.block_cache.replace_notification_blocks_with_file_id(file_id, notification);
+        self.append_block_cache_index_insert(file_id);
+
+        debug!(file_id, bytes_written, "Replaced notification in WAL");
 
         Ok(())
     }
 
     #[instrument(target = "exex::wal", skip(self))]
-    fn finalize(&self, to_block: BlockNumHash) -> eyre::Result<()> {
+    fn finalize(&self, to_block: BlockNumHash, split: bool) -> WalResult<()> {
+        let _write_lock = self.write_lock.lock();
+
         // First, walk cache to find the file ID of the notification with the finalized block and
         // save the file ID with the first unfinalized block. Do not remove any notifications
         // yet.
         let mut unfinalized_from_file_id = None;
+        let mut notification_to_split = None;
         {
             let mut block_cache = self.block_cache.iter().peekable();
             while let Some((file_id, block)) = block_cache.next() {
@@ -134,13 +748,16 @@ impl WalInner {
                     let notification = self
                         .storage
                         .read_notification(file_id)?
-                        .ok_or_eyre("notification not found")?;
+                        .ok_or(WalError::NotFound { file_id })?;
                     if notification.committed_chain().unwrap().blocks().len() == 1 {
                         unfinalized_from_file_id = Some(
                             block_cache.peek().map(|(file_id, _)| *file_id).unwrap_or(u64::MAX),
                         );
                     } else {
                         unfinalized_from_file_id = Some(file_id);
+                        if split {
+                            notification_to_split = Some((file_id, notification));
+                        }
                     }
 
                     debug!(
@@ -177,92 +794,354 @@ impl WalInner {
         debug!(?remove_to_file_id, "Block cache was finalized");
 
         // Remove notifications from the storage.
-        if let Some((file_range_start, file_range_end)) = file_range_start.zip(file_range_end) {
+        let removed_notifications = if let Some((file_range_start, file_range_end)) =
+            file_range_start.zip(file_range_end)
+        {
             let removed_notifications =
                 self.storage.remove_notifications(file_range_start..=file_range_end)?;
+            self.append_block_cache_index_remove(file_range_start..=file_range_end);
             debug!(?removed_notifications, "Storage was finalized");
+            removed_notifications
         } else {
             debug!("No notifications were finalized from the storage");
+            0
+        };
+
+        // Remove the finalized blocks from the notification with the first unfinalized block.
+        if let Some((file_id, notification)) = notification_to_split {
+            self.split_notification(file_id, notification, to_block.number)?;
         }
 
+        debug!(
+            removed_notifications,
+            cached_notifications = self.block_cache.notifications_count(),
+            "Finalized WAL"
+        );
+
         Ok(())
     }
 
-    /// Returns an iterator over all notifications in the WAL.
-    fn iter_notifications(
-        &self,
-    ) -> eyre::Result<Box<dyn Iterator<Item = eyre::Result<ExExNotification>> + '_>> {
-        let Some(range) = self.storage.files_range()? else {
-            return Ok(Box::new(std::iter::empty()))
-        };
+    #[instrument(target = "exex::wal", skip(self))]
+    fn compact(&self) -> WalResult<()> {
+        let _write_lock = self.write_lock.lock();
 
-        Ok(Box::new(self.storage.iter_notifications(range).map(|entry| Ok(entry?.1))))
-    }
-}
+        let file_ids = self.block_cache.file_ids();
+        if file_ids.iter().enumerate().all(|(new_file_id, file_id)| new_file_id as u64 == *file_id)
+        {
+            debug!("WAL is already compact");
+            return Ok(())
+        }
 
-/// A read-only handle to the WAL that can be shared.
-#[derive(Debug)]
-pub struct WalHandle {
-    wal: Arc<WalInner>,
-}
+        self.storage.compact(&file_ids)?;
+        *self.last_synced_at.lock() = Instant::now();
 
-impl WalHandle {
-    /// Returns the notification for the given committed block hash if it exists.
-    pub fn get_committed_notification_by_block_hash(
-        &self,
-        block_hash: &B256,
-    ) -> eyre::Result<Option<ExExNotification>> {
-        let Some(file_id) = self.wal.block_cache.get_file_id_by_committed_block_hash(block_hash)
-        else {
-            return Ok(None)
-        };
+        // The file IDs changed, so the index is persisted whole instead of appending the updates
+        self.block_cache.compact_file_ids();
+        self.mark_block_cache_index_outdated();
+        self.try_write_block_cache_index();
 
-        self.wal.storage.read_notification(file_id)
+        let compacted_files_count = file_ids.len();
+        debug!(compacted_files_count, "Compacted WAL");
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+    #[instrument(target = "exex::wal", skip(self))]
+    fn gc(&self) -> WalResult<Vec<u64>> {
+        let _write_lock = self.write_lock.lock();
 
-    use eyre::OptionExt;
-    use reth_exex_types::ExExNotification;
-    use reth_provider::Chain;
-    use reth_testing_utils::generators::{
-        self, random_block, random_block_range, BlockParams, BlockRangeParams,
-    };
+        self.storage.refresh()?;
 
-    use crate::wal::{
-        cache::{CachedBlock, CachedBlockAction},
-        Wal,
-    };
+        let orphaned_file_ids = self
+            .storage
+            .file_ids()?
+            .into_iter()
+            .filter(|file_id| !self.block_cache.contains_notification(*file_id))
+            .collect::<Vec<_>>();
+        for file_id in &orphaned_file_ids {
+            self.storage.remove_notifications(*file_id..=*file_id)?;
+        }
 
-    fn read_notifications(wal: &Wal) -> eyre::Result<Vec<ExExNotification>> {
-        let Some(files_range) = wal.inner.storage.files_range()? else { return Ok(Vec::new()) };
+        debug!(?orphaned_file_ids, "Removed orphaned notifications");
+        Ok(orphaned_file_ids)
+    }
 
-        wal.inner
-            .storage
-            .iter_notifications(files_range)
-            .map(|entry| Ok(entry?.1))
-            .collect::<eyre::Result<_>>()
+    fn flush(&self) -> WalResult<()> {
+        let _write_lock = self.write_lock.lock();
+
+        self.storage.sync()?;
+        *self.last_synced_at.lock() = Instant::now();
+        self.write_block_cache_index()?;
+        debug!(target: "exex::wal", "Flushed WAL");
+        Ok(())
     }
 
-    #[test]
-    fn test_wal() -> eyre::Result<()> {
-        reth_tracing::init_test_tracing();
+    #[instrument(target = "exex::wal", skip(self))]
+    fn drain(&self) -> WalResult<Vec<ExExNotification>> {
+        let _write_lock = self.write_lock.lock();
 
-        let mut rng = generators::rng();
+        let notifications = self.iter_notifications()?.collect::<WalResult<Vec<_>>>()?;
 
-        // Create an instance of the WAL in a temporary directory
-        let temp_dir = tempfile::tempdir()?;
-        let mut wal = Wal::new(&temp_dir)?;
-        assert!(wal.inner.block_cache.is_empty());
+        let removed_notifications = self.storage.clear()?;
+        self.block_cache.clear();
+        self.mark_block_cache_index_outdated();
+        self.try_write_block_cache_index();
 
-        // Create 4 canonical blocks and one reorged block with number 2
-        let blocks = random_block_range(&mut rng, 0..=3, BlockRangeParams::default())
-            .into_iter()
-            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
-            .collect::<eyre::Result<Vec<_>>>()?;
+        debug!(removed_notifications, "Drained WAL");
+
+        Ok(notifications)
+    }
+
+    /// Splits the committed notification with the given file ID at the given block number,
+    /// rewriting it with only the blocks higher than the block number.
+    #[instrument(target = "exex::wal", skip(self, notification))]
+    fn split_notification(
+        &self,
+        file_id: u64,
+        notification: ExExNotification,
+        block_number: BlockNumber,
+    ) -> WalResult<()> {
+        let ExExNotification::ChainCommitted { new } = notification else {
+            debug!("Only committed notifications can be split");
+            return Ok(())
+        };
+
+        match Arc::unwrap_or_clone(new).split(ChainSplitTarget::Number(block_number)) {
+            ChainSplit::Split { pending, .. } => {
+                let notification = ExExNotification::ChainCommitted { new: Arc::new(pending) };
+                self.storage.write_notification(file_id, &notification, true)?;
+                self.block_cache.replace_notification_blocks_with_file_id(file_id, &notification);
+                self.append_block_cache_index_insert(file_id);
+                debug!(
+                    committed_block_range = ?notification.committed_chain().map(|chain| chain.range()),
+                    "Split notification"
+                );
+            }
+            ChainSplit::NoSplitCanonical(_) => {
+                // All blocks of the notification are finalized
+                self.storage.remove_notifications(file_id..=file_id)?;
+                self.block_cache.remove_notification_blocks(file_id);
+                self.append_block_cache_index_remove(file_id..=file_id);
+                debug!("Removed fully finalized notification");
+            }
+            ChainSplit::NoSplitPending(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over all notifications in the WAL.
+    fn iter_notifications(
+        &self,
+    ) -> WalResult<Box<dyn Iterator<Item = WalResult<ExExNotification>> + '_>> {
+        let Some(range) = self.storage.files_range()? else {
+            return Ok(Box::new(std::iter::empty()))
+        };
+
+        Ok(Box::new(self.storage.iter_notifications(range).map(|entry| Ok(entry?.1))))
+    }
+    #[instrument(target = "exex::wal", skip_all)]
+    fn export(&self, mut writer: impl Write) -> WalResult<()> {
+        serde_json::to_writer(&mut writer, &serde_json::json!({ "version": WAL_EXPORT_VERSION }))
+            .map_err(WalError::Encode)?;
+        writeln!(writer)?;
+
+        let mut exported_notifications = 0;
+        if let Some(range) = self.storage.files_range()? {
+            for entry in self.storage.iter_notifications(range) {
+                let (file_id, notification) = entry?;
+                // TODO(alexey): use rmp-serde when Alloy and Reth serde issues are resolved
+                serde_json::to_writer(&mut writer, &(file_id, notification))
+                    .map_err(WalError::Encode)?;
+                writeln!(writer)?;
+                exported_notifications += 1;
+            }
+        }
+        writer.flush()?;
+
+        debug!(?exported_notifications, "Exported WAL");
+
+        Ok(())
+    }
+
+    #[instrument(target = "exex::wal", skip_all)]
+    fn import(storage: Box<dyn Storage>, reader: impl Read) -> WalResult<Self> {
+        if let Some(range) = storage.files_range()? {
+            return Err(WalError::StorageNotEmpty(range))
+        }
+
+        let mut lines = BufReader::new(reader).lines();
+
+        let header = lines
+            .next()
+            .ok_or_else(|| WalError::InvalidExport("header is missing".to_string()))??;
+        let header: serde_json::Value = serde_json::from_str(&header).map_err(WalError::Decode)?;
+        let version = header
+            .get("version")
+            .and_then(|version| version.as_u64())
+            .ok_or_else(|| WalError::InvalidExport("version is missing".to_string()))?;
+        if version != WAL_EXPORT_VERSION {
+            return Err(WalError::VersionUnsupported(version))
+        }
+
+        let wal = Self::empty(storage);
+        let mut last_file_id = None;
+        for line in lines {
+            // TODO(alexey): use rmp-serde when Alloy and Reth serde issues are resolved
+            let (file_id, notification): (u64, ExExNotification) =
+                serde_json::from_str(&line?).map_err(WalError::Decode)?;
+            if last_file_id.is_some_and(|last_file_id| file_id <= last_file_id) {
+                return Err(WalError::InvalidExport(format!(
+                    "file IDs are not ascending: {file_id} after {last_file_id:?}"
+                )))
+            }
+            last_file_id = Some(file_id);
+
+            wal.storage.write_notification(file_id, &notification, false)?;
+            wal.block_cache.insert_notification_blocks_with_file_id(file_id, &notification);
+        }
+        wal.storage.sync()?;
+        wal.mark_block_cache_index_outdated();
+        wal.write_block_cache_index()?;
+
+        debug!(?last_file_id, "Imported WAL");
+
+        Ok(wal)
+    }
+
+    /// Returns an iterator over the notifications in the WAL that intersect the given block range.
+    fn iter_range(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> WalResult<impl Iterator<Item = WalResult<ExExNotification>> + '_> {
+        let file_ids = self.block_cache.get_file_ids_by_block_range(&range);
+        debug!(?range, ?file_ids, "Found notifications for the block range");
+
+        Ok(file_ids.into_iter().map(|file_id| {
+            self.storage.read_notification(file_id)?.ok_or(WalError::NotFound { file_id })
+        }))
+    }
+
+    /// Returns an iterator over the notifications in the WAL that touch at least one of the given
+    /// addresses.
+    fn stream_filtered<'a>(
+        &'a self,
+        addresses: &'a HashSet<Address>,
+    ) -> WalResult<impl Iterator<Item = WalResult<ExExNotification>> + 'a> {
+        let file_ids = self.block_cache.get_file_ids_by_touched_addresses(addresses);
+        debug!(?file_ids, "Found notifications that may touch the addresses");
+
+        Ok(file_ids
+            .into_iter()
+            .map(|file_id| {
+                self.storage.read_notification(file_id)?.ok_or(WalError::NotFound { file_id })
+            })
+            .filter(|notification| {
+                notification.as_ref().map_or(true, |notification| {
+                    notification
+                        .committed_chain()
+                        .into_iter()
+                        .chain(notification.reverted_chain())
+                        .any(|chain| {
+                            chain.touched_addresses().any(|address| addresses.contains(&address))
+                        })
+                })
+            }))
+    }
+}
+
+impl Drop for WalInner {
+    fn drop(&mut self) {
+        self.try_write_block_cache_index();
+    }
+}
+
+/// A read-only handle to the WAL that can be shared.
+#[derive(Debug)]
+pub struct WalHandle {
+    wal: Arc<WalInner>,
+}
+
+impl WalHandle {
+    /// Returns the notification for the given committed block hash if it exists.
+    pub fn get_committed_notification_by_block_hash(
+        &self,
+        block_hash: &B256,
+    ) -> WalResult<Option<ExExNotification>> {
+        let Some(file_id) = self.wal.block_cache.get_file_id_by_committed_block_hash(block_hash)
+        else {
+            return Ok(None)
+        };
+
+        self.wal.storage.read_notification(file_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        io::Write,
+        ops::RangeInclusive,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use alloy_primitives::{Address, Bytes, Log};
+    use eyre::OptionExt;
+    use parking_lot::Mutex;
+    use reth_exex_types::ExExNotification;
+    use reth_primitives::{Receipt, Receipts};
+    use reth_provider::{Chain, ExecutionOutcome};
+    use reth_testing_utils::generators::{
+        self, random_block, random_block_range, BlockParams, BlockRangeParams,
+    };
+
+    use crate::wal::{
+        cache::{CachedBlock, CachedBlockAction},
+        FileStorage, InMemoryStorage, Storage, Wal, WalDeduplication, WalDurability, WalError,
+        WalInner, WalLayout, WalResult, ASYNC_COMMIT_QUEUE_CAPACITY,
+    };
+
+    fn read_notifications(wal: &Wal) -> eyre::Result<Vec<ExExNotification>> {
+        let Some(files_range) = wal.inner.storage.files_range()? else { return Ok(Vec::new()) };
+
+        wal.inner
+            .storage
+            .iter_notifications(files_range)
+            .map(|entry| Ok(entry?.1))
+            .collect::<eyre::Result<_>>()
+    }
+
+    #[test]
+    fn test_wal() -> eyre::Result<()> {
+        // Create an instance of the WAL in a temporary directory
+        let temp_dir = tempfile::tempdir()?;
+        test_wal_commit_and_finalize(Wal::new(&temp_dir)?)
+    }
+
+    #[test]
+    fn test_wal_in_memory() -> eyre::Result<()> {
+        test_wal_commit_and_finalize(Wal::new_in_memory())
+    }
+
+    fn test_wal_commit_and_finalize(mut wal: Wal) -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        assert!(wal.inner.block_cache.is_empty());
+        assert_eq!(wal.earliest_block(), None);
+        assert_eq!(wal.latest_block(), None);
+
+        // Create 4 canonical blocks and one reorged block with number 2
+        let blocks = random_block_range(&mut rng, 0..=3, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
         let block_1_reorged = random_block(
             &mut rng,
             1,
@@ -339,6 +1218,8 @@ mod tests {
             committed_notification_1_cache
         );
         assert_eq!(read_notifications(&wal)?, vec![committed_notification_1.clone()]);
+        assert_eq!(wal.earliest_block(), Some((blocks[0].number, blocks[0].hash()).into()));
+        assert_eq!(wal.latest_block(), Some((blocks[1].number, blocks[1].hash()).into()));
 
         // Second notification (revert block 1)
         wal.commit(&reverted_notification)?;
@@ -359,6 +1240,8 @@ mod tests {
             read_notifications(&wal)?,
             vec![committed_notification_1.clone(), reverted_notification.clone()]
         );
+        // The tail notification is a pure revert, so the latest block is the reverted one
+        assert_eq!(wal.latest_block(), Some((blocks[1].number, blocks[1].hash()).into()));
 
         // Third notification (commit block 1, 2)
         wal.commit(&committed_notification_2)?;
@@ -458,6 +1341,1222 @@ mod tests {
             [committed_notification_2_cache, reorged_notification_cache].concat()
         );
         assert_eq!(read_notifications(&wal)?, vec![committed_notification_2, reorged_notification]);
+        assert_eq!(
+            wal.earliest_block(),
+            Some((block_1_reorged.number, block_1_reorged.hash()).into())
+        );
+        assert_eq!(wal.latest_block(), Some((blocks[3].number, blocks[3].hash()).into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_finalize_split() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?.with_split_on_finalize(true);
+
+        // Commit a single notification with 3 blocks and a receipt per block
+        let blocks = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let receipts = (0..3)
+            .map(|i| Receipt { cumulative_gas_used: i, ..Default::default() })
+            .collect::<Vec<_>>();
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(
+                blocks.clone(),
+                ExecutionOutcome::new(
+                    Default::default(),
+                    Receipts {
+                        receipt_vec: receipts.iter().cloned().map(|r| vec![Some(r)]).collect(),
+                    },
+                    0,
+                    Vec::new(),
+                ),
+                None,
+            )),
+        };
+        wal.commit(&notification)?;
+
+        // Finalize into the middle of the notification
+        wal.finalize((blocks[1].number, blocks[1].hash()).into())?;
+
+        // Only the tail block is left, with its receipt
+        let notifications = read_notifications(&wal)?;
+        assert_eq!(notifications.len(), 1);
+        let chain = notifications[0].committed_chain().ok_or_eyre("no committed chain")?;
+        assert_eq!(chain.blocks().values().cloned().collect::<Vec<_>>(), vec![blocks[2].clone()]);
+        assert_eq!(
+            chain.execution_outcome().receipts().receipt_vec,
+            vec![vec![Some(receipts[2].clone())]]
+        );
+        assert_eq!(
+            wal.inner.block_cache.iter().collect::<Vec<_>>(),
+            vec![(
+                0,
+                CachedBlock {
+                    action: CachedBlockAction::Commit,
+                    block: (blocks[2].number, blocks[2].hash()).into(),
+                    parent_hash: blocks[2].parent_hash,
+                },
+            )]
+        );
+
+        // The finalized blocks can't be looked up by hash anymore
+        for block in &blocks[..2] {
+            assert_eq!(
+                wal.inner.block_cache.get_file_id_by_committed_block_hash(&block.hash()),
+                None
+            );
+        }
+        assert_eq!(
+            wal.inner.block_cache.get_file_id_by_committed_block_hash(&blocks[2].hash()),
+            Some(0)
+        );
+
+        // The split notification survives reopening the WAL
+        drop(wal);
+        assert_eq!(read_notifications(&Wal::new(&temp_dir)?)?, notifications);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_replace_entry() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let blocks = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let mut notifications = blocks
+            .iter()
+            .map(|block| ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block.clone()], Default::default(), None)),
+            })
+            .collect::<Vec<_>>();
+        for notification in &notifications {
+            wal.commit(notification)?;
+        }
+
+        // Replace the middle notification with a different block 1
+        let block_1_reorged = random_block(
+            &mut rng,
+            1,
+            BlockParams { parent: Some(blocks[0].hash()), ..Default::default() },
+        )
+        .seal_with_senders()
+        .ok_or_eyre("failed to recover senders")?;
+        notifications[1] = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block_1_reorged.clone()], Default::default(), None)),
+        };
+        wal.replace_entry(1, &notifications[1])?;
+
+        // The surrounding file IDs are unchanged
+        assert_eq!(read_notifications(&wal)?, notifications);
+        assert_eq!(
+            wal.inner
+                .block_cache
+                .iter()
+                .map(|(file_id, block)| (file_id, block.block))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, (blocks[0].number, blocks[0].hash()).into()),
+                (1, (block_1_reorged.number, block_1_reorged.hash()).into()),
+                (2, (blocks[2].number, blocks[2].hash()).into()),
+            ]
+        );
+
+        // The replaced block can't be found by its hash anymore
+        let handle = wal.handle();
+        assert_eq!(handle.get_committed_notification_by_block_hash(&blocks[1].hash())?, None);
+        assert_eq!(
+            handle.get_committed_notification_by_block_hash(&block_1_reorged.hash())?,
+            Some(notifications[1].clone())
+        );
+
+        // Replacing a non-existent notification fails
+        assert!(matches!(
+            wal.replace_entry(3, &notifications[1]),
+            Err(WalError::NotFound { file_id: 3 })
+        ));
+        assert_eq!(read_notifications(&wal)?, notifications);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wal_commit_async() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let wal = Wal::new(&temp_dir)?;
+
+        let notifications = random_block_range(&mut rng, 0..=31, Default::default())
+            .into_iter()
+            .map(|block| {
+                Ok(ExExNotification::ChainCommitted {
+                    new: Arc::new(Chain::new(
+                        vec![block.seal_with_senders().ok_or_eyre("failed to recover senders")?],
+                        Default::default(),
+                        None,
+                    )),
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        // Submit more notifications than the queue can hold at once
+        assert!(notifications.len() > ASYNC_COMMIT_QUEUE_CAPACITY);
+        futures::future::try_join_all(
+            notifications.iter().map(|notification| wal.commit_async(notification.clone())),
+        )
+        .await?;
+
+        // The notifications are committed in the submission order
+        assert_eq!(read_notifications(&wal)?, notifications);
+        assert_eq!(
+            wal.inner.block_cache.iter().map(|(file_id, _)| file_id).collect::<Vec<_>>(),
+            (0..notifications.len() as u64).collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_concurrent_commits() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let wal = Wal::new(&temp_dir)?.with_durability(WalDurability::None);
+
+        let notifications = random_block_range(&mut rng, 0..=31, Default::default())
+            .into_iter()
+            .map(|block| {
+                Ok(ExExNotification::ChainCommitted {
+                    new: Arc::new(Chain::new(
+                        vec![block.seal_with_senders().ok_or_eyre("failed to recover senders")?],
+                        Default::default(),
+                        None,
+                    )),
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        // Commit the notifications from multiple clones of the WAL at the same time
+        std::thread::scope(|scope| {
+            let handles = notifications
+                .chunks(8)
+                .map(|chunk| {
+                    let mut wal = wal.clone();
+                    scope.spawn(move || {
+                        chunk.iter().try_for_each(|notification| wal.commit(notification))
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().try_for_each(|handle| handle.join().unwrap())
+        })?;
+
+        // Every notification is committed under its own file ID
+        assert_eq!(wal.files_count()?, notifications.len());
+        let committed = read_notifications(&wal)?;
+        assert!(notifications.iter().all(|notification| committed.contains(notification)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_stream_filtered() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let address_1 = Address::with_last_byte(1);
+        let address_2 = Address::with_last_byte(2);
+        let address_3 = Address::with_last_byte(3);
+
+        // Create a committed notification per block, with a single receipt containing a log
+        // emitted by the given address
+        let blocks = random_block_range(
+            &mut rng,
+            0..=2,
+            BlockRangeParams { tx_count: 0..1, ..Default::default() },
+        )
+        .into_iter()
+        .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+        .collect::<eyre::Result<Vec<_>>>()?;
+        let notifications = blocks
+            .iter()
+            .zip([address_1, address_2, address_3])
+            .map(|(block, address)| ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(
+                    vec![block.clone()],
+                    ExecutionOutcome::new(
+                        Default::default(),
+                        Receipts {
+                            receipt_vec: vec![vec![Some(Receipt {
+                                logs: vec![Log::new_unchecked(address, vec![], Bytes::new())],
+                                ..Default::default()
+                            })]],
+                        },
+                        block.number,
+                        Vec::new(),
+                    ),
+                    None,
+                )),
+            })
+            .collect::<Vec<_>>();
+        for notification in &notifications {
+            wal.commit(notification)?;
+        }
+
+        // No addresses match nothing
+        assert_eq!(wal.stream_filtered(&HashSet::new())?.collect::<WalResult<Vec<_>>>()?, []);
+
+        // A single address matches only the notification that touches it
+        assert_eq!(
+            wal.stream_filtered(&HashSet::from([address_2]))?.collect::<WalResult<Vec<_>>>()?,
+            vec![notifications[1].clone()]
+        );
+
+        // Multiple addresses match the notifications in order
+        assert_eq!(
+            wal.stream_filtered(&HashSet::from([address_3, address_1, Address::random()]))?
+                .collect::<WalResult<Vec<_>>>()?,
+            vec![notifications[0].clone(), notifications[2].clone()]
+        );
+
+        // The touched addresses are persisted with the block cache index
+        drop(wal);
+        let wal = Wal::new(&temp_dir)?;
+        assert!(wal
+            .inner
+            .block_cache
+            .get_file_ids_by_touched_addresses(&HashSet::new())
+            .is_empty());
+
+        // Notifications that don't touch the addresses are not read from the storage
+        std::fs::write(temp_dir.path().join("0.wal"), b"corrupted")?;
+        assert_eq!(
+            wal.stream_filtered(&HashSet::from([address_2]))?.collect::<WalResult<Vec<_>>>()?,
+            vec![notifications[1].clone()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_iter_range() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        // Commit two blocks per notification
+        let blocks = random_block_range(&mut rng, 0..=7, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let notifications = blocks
+            .chunks(2)
+            .map(|blocks| ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(blocks.to_vec(), Default::default(), None)),
+            })
+            .collect::<Vec<_>>();
+        for notification in &notifications {
+            wal.commit(notification)?;
+        }
+
+        // Range in the middle spanning notification boundaries includes the notifications whole
+        assert_eq!(
+            wal.iter_range(3..=4)?.collect::<WalResult<Vec<_>>>()?,
+            vec![notifications[1].clone(), notifications[2].clone()]
+        );
+
+        // Range inside a single notification
+        assert_eq!(
+            wal.iter_range(5..=5)?.collect::<WalResult<Vec<_>>>()?,
+            vec![notifications[2].clone()]
+        );
+
+        // Range outside of the WAL
+        assert_eq!(wal.iter_range(10..=20)?.collect::<WalResult<Vec<_>>>()?, []);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_block_cache_index() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let blocks = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        for block in &blocks {
+            wal.commit(&ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block.clone()], Default::default(), None)),
+            })?;
+        }
+        let cached_blocks = wal.inner.block_cache.iter().collect::<Vec<_>>();
+
+        // The commits are appended to the index as updates, and the index itself is only
+        // persisted when the WAL is flushed or closed
+        let index_path = temp_dir.path().join("block_cache.index");
+        let updates_path = temp_dir.path().join("block_cache.updates");
+        assert!(!index_path.exists());
+        assert!(updates_path.exists());
+
+        // Simulate a crash before the index is persisted
+        wal.inner.is_block_cache_index_outdated.store(false, Ordering::Relaxed);
+        drop(wal);
+        assert!(!index_path.exists());
+
+        // With a valid index, the notifications are not decoded on startup, so a corrupted
+        // notification with the same length and modification time doesn't prevent the WAL from
+        // opening
+        let notification_1_path = temp_dir.path().join("1.wal");
+        let notification_1 = std::fs::read(&notification_1_path)?;
+        let modified = std::fs::metadata(&notification_1_path)?.modified()?;
+        std::fs::write(&notification_1_path, vec![b' '; notification_1.len()])?;
+        std::fs::File::options().write(true).open(&notification_1_path)?.set_modified(modified)?;
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(wal.inner.block_cache.iter().collect::<Vec<_>>(), cached_blocks);
+        assert_eq!(
+            wal.handle().get_committed_notification_by_block_hash(&blocks[2].hash())?,
+            Some(ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![blocks[2].clone()], Default::default(), None)),
+            })
+        );
+
+        // Closing the WAL persists the index with the updates included
+        drop(wal);
+        assert!(index_path.exists());
+        assert!(!updates_path.exists());
+
+        // A notification rewritten in place makes the index stale, so the block cache is filled
+        // from the notifications
+        std::fs::write(&notification_1_path, b"not a notification")?;
+        assert!(matches!(Wal::new(&temp_dir), Err(WalError::Corrupt { file_id: 1, .. })));
+        std::fs::write(&notification_1_path, &notification_1)?;
+        let mut wal = Wal::new(&temp_dir)?;
+        assert_eq!(wal.inner.block_cache.iter().collect::<Vec<_>>(), cached_blocks);
+
+        // Simulate a crash after replacing a notification, before the index is persisted. The
+        // replacement is appended to the index, so the block cache is loaded from it.
+        wal.replace_entry(
+            0,
+            &ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(blocks[..2].to_vec(), Default::default(), None)),
+            },
+        )?;
+        wal.inner.is_block_cache_index_outdated.store(false, Ordering::Relaxed);
+        drop(wal);
+        assert!(updates_path.exists());
+
+        let modified = std::fs::metadata(&notification_1_path)?.modified()?;
+        std::fs::write(&notification_1_path, vec![b' '; notification_1.len()])?;
+        std::fs::File::options().write(true).open(&notification_1_path)?.set_modified(modified)?;
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(wal.inner.block_cache.notifications_count(), 3);
+        assert_eq!(
+            wal.inner
+                .block_cache
+                .iter()
+                .filter(|(file_id, _)| *file_id == 0)
+                .map(|(_, block)| block.block.hash)
+                .collect::<Vec<_>>(),
+            [blocks[0].hash(), blocks[1].hash()]
+        );
+        drop(wal);
+        std::fs::write(&notification_1_path, &notification_1)?;
+        std::fs::File::options().write(true).open(&notification_1_path)?.set_modified(modified)?;
+
+        // A lost update makes the index stale, so the block cache is filled from the
+        // notifications
+        let mut wal = Wal::new(&temp_dir)?;
+        wal.commit(&ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![blocks[0].clone()], Default::default(), None)),
+        })?;
+        wal.inner.is_block_cache_index_outdated.store(false, Ordering::Relaxed);
+        drop(wal);
+        std::fs::remove_file(&updates_path)?;
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(wal.inner.block_cache.notifications_count(), 4);
+        assert_eq!(wal.inner.block_cache.back().map(|(file_id, _)| file_id), Some(3));
+        drop(wal);
+        std::fs::remove_file(temp_dir.path().join("3.wal"))?;
+
+        // A notification added outside of the WAL makes the index stale
+        std::fs::copy(temp_dir.path().join("2.wal"), temp_dir.path().join("3.wal"))?;
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(wal.inner.block_cache.notifications_count(), 4);
+        assert_eq!(wal.inner.block_cache.back().map(|(file_id, _)| file_id), Some(3));
+
+        // Simulate a crash after finalizing, before the index is persisted. The removal is
+        // appended to the index, so it stays valid.
+        wal.finalize(blocks[2].num_hash())?;
+        wal.inner.is_block_cache_index_outdated.store(false, Ordering::Relaxed);
+        drop(wal);
+        let mut inner = WalInner::empty(Box::new(FileStorage::new(&temp_dir)?));
+        assert!(inner.load_block_cache_index()?);
+        assert_eq!(inner.block_cache.file_ids(), [3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_truncated_tail() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let blocks = random_block_range(&mut rng, 0..=3, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let notifications = blocks
+            .into_iter()
+            .map(|block| ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+            })
+            .collect::<Vec<_>>();
+        wal.commit(&notifications[0])?;
+        wal.commit(&notifications[1])?;
+        drop(wal);
+
+        // The node crashed while writing the last notification
+        let data = std::fs::read(temp_dir.path().join("1.wal"))?;
+        std::fs::write(temp_dir.path().join("2.wal"), &data[..data.len() / 2])?;
+
+        // The truncated notification is dropped, and the intact ones are loaded
+        let mut wal = Wal::new(&temp_dir)?;
+        assert!(!temp_dir.path().join("2.wal").exists());
+        assert_eq!(read_notifications(&wal)?, notifications[..2]);
+        assert_eq!(
+            wal.inner.block_cache.iter().map(|(file_id, _)| file_id).collect::<Vec<_>>(),
+            [0, 1]
+        );
+
+        // The next notification is written in place of the truncated one
+        wal.commit(&notifications[2])?;
+        assert_eq!(read_notifications(&wal)?, notifications[..3]);
+        assert_eq!(wal.inner.block_cache.back().map(|(file_id, _)| file_id), Some(2));
+        drop(wal);
+
+        // A legacy notification in the last file is not removed
+        std::fs::write(temp_dir.path().join("3.wal"), serde_json::to_vec(&notifications[3])?)?;
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(read_notifications(&wal)?, notifications);
+        drop(wal);
+
+        // A complete notification that can't be decoded is reported and not removed
+        std::fs::write(temp_dir.path().join("4.wal"), br#"{"version":1,"notification":{}}"#)?;
+        assert!(matches!(Wal::new(&temp_dir), Err(WalError::Corrupt { file_id: 4, .. })));
+        assert!(temp_dir.path().join("4.wal").exists());
+
+        // A read-only WAL doesn't remove the truncated notification
+        std::fs::write(temp_dir.path().join("4.wal"), b"{\"version\":1,")?;
+        assert!(matches!(
+            Wal::open_read_only(&temp_dir),
+            Err(WalError::Corrupt { file_id: 4, .. })
+        ));
+        assert!(temp_dir.path().join("4.wal").exists());
+
+        let wal = Wal::new(&temp_dir)?;
+        assert!(!temp_dir.path().join("4.wal").exists());
+        assert_eq!(read_notifications(&wal)?, notifications);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_files_count() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+        assert_eq!(wal.directory(), Some(temp_dir.path()));
+        assert_eq!(wal.files_count()?, 0);
+
+        let blocks = random_block_range(&mut rng, 0..=3, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        for (i, block) in blocks.iter().enumerate() {
+            wal.commit(&ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block.clone()], Default::default(), None)),
+            })?;
+            assert_eq!(wal.files_count()?, i + 1);
+        }
+
+        // Finalized notifications are not counted
+        wal.finalize(blocks[0].num_hash())?;
+        assert_eq!(wal.files_count()?, 3);
+
+        // Missing files in the middle of the WAL are not counted
+        let moved_dir = tempfile::tempdir()?;
+        let moved_path = moved_dir.path().join("2.wal");
+        std::fs::rename(temp_dir.path().join("2.wal"), &moved_path)?;
+        assert_eq!(wal.files_count()?, 2);
+        std::fs::rename(&moved_path, temp_dir.path().join("2.wal"))?;
+        assert_eq!(wal.files_count()?, 3);
+
+        wal.drain()?;
+        assert_eq!(wal.files_count()?, 0);
+
+        // In-memory WAL has no directory
+        assert_eq!(Wal::new_in_memory().directory(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_last_committed_block() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=3, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let mut wal = Wal::new_in_memory();
+        assert_eq!(wal.last_committed_block(), None);
+
+        wal.commit(&ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(blocks[..2].to_vec(), Default::default(), None)),
+        })?;
+        wal.commit(&ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(blocks[2..].to_vec(), Default::default(), None)),
+        })?;
+        assert_eq!(wal.last_committed_block(), Some(blocks[3].num_hash()));
+
+        // The tail notification reverts the last block
+        wal.commit(&ExExNotification::ChainReverted {
+            old: Arc::new(Chain::new(vec![blocks[3].clone()], Default::default(), None)),
+        })?;
+        assert_eq!(wal.latest_block(), Some(blocks[3].num_hash()));
+        assert_eq!(wal.last_committed_block(), Some(blocks[2].num_hash()));
+
+        // Reverting down to the first block skips the whole second notification
+        wal.commit(&ExExNotification::ChainReverted {
+            old: Arc::new(Chain::new(vec![blocks[2].clone()], Default::default(), None)),
+        })?;
+        assert_eq!(wal.last_committed_block(), Some(blocks[1].num_hash()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_deduplication() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=1, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let committed = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(blocks.clone(), Default::default(), None)),
+        };
+        let reverted = ExExNotification::ChainReverted {
+            old: Arc::new(Chain::new(blocks, Default::default(), None)),
+        };
+
+        for (deduplication, files_count) in [
+            (WalDeduplication::Disabled, 3),
+            (WalDeduplication::Skip, 2),
+            (WalDeduplication::Reject, 2),
+        ] {
+            let mut wal = Wal::new_in_memory().with_deduplication(deduplication);
+            wal.commit(&committed)?;
+
+            let result = wal.commit(&committed);
+            if deduplication == WalDeduplication::Reject {
+                assert!(matches!(result, Err(WalError::DuplicateNotification { file_id: 0 })));
+            } else {
+                result?;
+            }
+
+            // The revert of the same blocks is not a duplicate
+            wal.commit(&reverted)?;
+            assert_eq!(wal.files_count()?, files_count, "{deduplication:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_new_directory() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let temp_dir = tempfile::tempdir()?;
+        let directory = temp_dir.path().join("exex").join("wal");
+        assert!(!directory.exists());
+
+        let wal = Wal::new(&directory)?;
+        assert!(directory.is_dir());
+        assert_eq!(wal.directory(), Some(directory.as_path()));
+        assert_eq!(wal.files_count()?, 0);
+        assert_eq!(read_notifications(&wal)?, []);
+        assert!(wal.inner.block_cache.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_sharded_layout() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+
+        let block =
+            random_block(&mut rng, 0, BlockParams { tx_count: Some(0), ..Default::default() })
+                .seal_with_senders()
+                .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+
+        // Write the notifications directly to the storage, so that the block cache index isn't
+        // rewritten on every commit
+        let storage = FileStorage::new_with_layout(&temp_dir, WalLayout::Sharded)?;
+        for file_id in 0..2500 {
+            storage.write_notification(file_id, &notification, false)?;
+        }
+        storage.sync()?;
+        drop(storage);
+
+        // The files are spread across the shards
+        for (shard, files) in [("0", 1000), ("1", 1000), ("2", 500)] {
+            assert_eq!(std::fs::read_dir(temp_dir.path().join(shard))?.count(), files);
+        }
+        assert!(!temp_dir.path().join("0.wal").exists());
+
+        // The files are discovered across the shards
+        let wal = Wal::new_with_layout(&temp_dir, WalLayout::Sharded)?;
+        assert_eq!(wal.files_count()?, 2500);
+        let notifications = read_notifications(&wal)?;
+        assert_eq!(notifications.len(), 2500);
+        assert!(notifications.iter().all(|entry| entry == &notification));
+
+        // Empty shards are removed
+        wal.inner.storage.remove_notifications(0..=999)?;
+        assert!(!temp_dir.path().join("0").exists());
+        wal.inner.storage.remove_notifications(1000..=2499)?;
+        assert!(!temp_dir.path().join("1").exists());
+        assert!(!temp_dir.path().join("2").exists());
+        drop(wal);
+
+        // The flat layout doesn't accept shard directories
+        let storage = FileStorage::new_with_layout(&temp_dir, WalLayout::Sharded)?;
+        storage.write_notification(0, &notification, true)?;
+        drop(storage);
+        assert!(matches!(Wal::new(&temp_dir), Err(WalError::InvalidFileName(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_drain() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        // Draining an empty WAL is a no-op
+        assert_eq!(wal.drain()?, []);
+
+        let blocks = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let notifications = blocks
+            .into_iter()
+            .map(|block| ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+            })
+            .collect::<Vec<_>>();
+        for notification in &notifications[..2] {
+            wal.commit(notification)?;
+        }
+
+        // All notifications are returned in order, and the WAL is empty afterwards
+        assert_eq!(wal.drain()?, notifications[..2]);
+        assert_eq!(read_notifications(&wal)?, []);
+        assert!(wal.inner.block_cache.is_empty());
+        assert_eq!(wal.earliest_block(), None);
+
+        // The file IDs start from zero again
+        wal.commit(&notifications[2])?;
+        assert_eq!(wal.inner.block_cache.front().map(|(file_id, _)| file_id), Some(0));
+        drop(wal);
+
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(read_notifications(&wal)?, notifications[2..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_errors() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let blocks = random_block_range(&mut rng, 0..=1, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        for block in blocks {
+            wal.commit(&ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+            })?;
+        }
+
+        // The notification file was removed outside of the WAL
+        std::fs::remove_file(temp_dir.path().join("1.wal"))?;
+        assert!(matches!(
+            wal.iter_range(1..=1)?.collect::<WalResult<Vec<_>>>(),
+            Err(WalError::NotFound { file_id: 1 })
+        ));
+
+        // The notification file can't be decoded
+        std::fs::write(temp_dir.path().join("0.wal"), b"not a notification")?;
+        assert!(matches!(
+            wal.iter_range(0..=0)?.collect::<WalResult<Vec<_>>>(),
+            Err(WalError::Corrupt { file_id: 0, .. })
+        ));
+
+        // The errors can be converted into `eyre` reports
+        let report = eyre::Report::from(WalError::NotFound { file_id: 1 });
+        assert_eq!(report.to_string(), "notification 1 not found");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_export_import() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let blocks = random_block_range(&mut rng, 0..=3, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        for block in &blocks {
+            wal.commit(&ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block.clone()], Default::default(), None)),
+            })?;
+        }
+        // Finalize the first block, so that the file IDs don't start from zero
+        wal.finalize((blocks[0].number, blocks[0].hash()).into())?;
+
+        let mut export = Vec::new();
+        wal.export(&mut export)?;
+
+        // Export is deterministic
+        let mut second_export = Vec::new();
+        wal.export(&mut second_export)?;
+        assert_eq!(export, second_export);
+
+        let import_dir = tempfile::tempdir()?;
+        let imported_wal = Wal::import(&import_dir, export.as_slice())?;
+        assert_eq!(read_notifications(&imported_wal)?, read_notifications(&wal)?);
+        assert_eq!(
+            imported_wal.inner.block_cache.iter().collect::<Vec<_>>(),
+            wal.inner.block_cache.iter().collect::<Vec<_>>()
+        );
+
+        // The imported WAL is the same after reopening, and can be finalized
+        drop(imported_wal);
+        let imported_wal = Wal::new(&import_dir)?;
+        assert_eq!(read_notifications(&imported_wal)?, read_notifications(&wal)?);
+        imported_wal.finalize((blocks[2].number, blocks[2].hash()).into())?;
+        assert_eq!(
+            read_notifications(&imported_wal)?,
+            vec![ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![blocks[3].clone()], Default::default(), None)),
+            }]
+        );
+
+        // Importing into a non-empty directory fails
+        drop(imported_wal);
+        assert!(Wal::import(&import_dir, export.as_slice()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_lock() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+        wal.commit(&notification)?;
+
+        // The directory can't be opened again while the WAL is alive
+        assert!(Wal::new(&temp_dir).is_err());
+        assert!(Wal::open_read_only(&temp_dir).is_err());
+        drop(wal);
+
+        // Multiple readers can open the directory at the same time, but no writers
+        let mut reader = Wal::open_read_only(&temp_dir)?;
+        let other_reader = Wal::open_read_only(&temp_dir)?;
+        assert!(Wal::new(&temp_dir).is_err());
+        assert_eq!(read_notifications(&reader)?, vec![notification.clone()]);
+        assert_eq!(read_notifications(&other_reader)?, vec![notification.clone()]);
+        assert!(reader.commit(&notification).is_err());
+        drop((reader, other_reader));
+
+        // The directory can be opened for writing again after all readers are dropped
+        assert_eq!(read_notifications(&Wal::new(&temp_dir)?)?, vec![notification]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_logging() -> eyre::Result<()> {
+        /// Writer appending the logs to a shared buffer.
+        #[derive(Clone, Default)]
+        struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for LogBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+
+        let blocks = random_block_range(&mut rng, 0..=1, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let logs = LogBuffer::default();
+        let subscriber = reth_tracing::tracing_subscriber::fmt()
+            .with_max_level(reth_tracing::tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        reth_tracing::tracing::subscriber::with_default(subscriber, || {
+            for block in &blocks {
+                wal.commit(&ExExNotification::ChainCommitted {
+                    new: Arc::new(Chain::new(vec![block.clone()], Default::default(), None)),
+                })?;
+            }
+            wal.finalize((blocks[0].number, blocks[0].hash()).into())
+        })?;
+
+        let logs = String::from_utf8(logs.0.lock().clone())?;
+        let commit_logs = logs
+            .lines()
+            .filter(|line| line.contains("Committed notification to WAL"))
+            .collect::<Vec<_>>();
+        assert_eq!(commit_logs.len(), blocks.len());
+        for (file_id, line) in commit_logs.into_iter().enumerate() {
+            assert!(line.contains(&format!("file_id={file_id}")), "{line}");
+            assert!(line.contains("bytes_written="), "{line}");
+            assert!(line.contains(&format!("cached_notifications={}", file_id + 1)), "{line}");
+        }
+        let finalize_log = logs
+            .lines()
+            .find(|line| line.contains("Finalized WAL"))
+            .ok_or_eyre("finalize log not found")?;
+        assert!(finalize_log.contains("removed_notifications=1"), "{finalize_log}");
+        assert!(finalize_log.contains("cached_notifications=1"), "{finalize_log}");
+
+        Ok(())
+    }
+
+    /// [`InMemoryStorage`] that counts the number of times the notifications were synced.
+    #[derive(Debug)]
+    struct SyncCountingStorage {
+        inner: InMemoryStorage,
+        syncs: Arc<AtomicUsize>,
+    }
+
+    impl Storage for SyncCountingStorage {
+        fn files_range(&self) -> WalResult<Option<RangeInclusive<u64>>> {
+            self.inner.files_range()
+        }
+
+        fn remove_notifications(&self, range: RangeInclusive<u64>) -> WalResult<usize> {
+            self.inner.remove_notifications(range)
+        }
+
+        fn read_notification(&self, file_id: u64) -> WalResult<Option<ExExNotification>> {
+            self.inner.read_notification(file_id)
+        }
+
+        fn write_notification(
+            &self,
+            file_id: u64,
+            notification: &ExExNotification,
+            sync: bool,
+        ) -> WalResult<u64> {
+            if sync {
+                self.syncs.fetch_add(1, Ordering::Relaxed);
+            }
+            self.inner.write_notification(file_id, notification, sync)
+        }
+
+        fn sync(&self) -> WalResult<()> {
+            self.syncs.fetch_add(1, Ordering::Relaxed);
+            self.inner.sync()
+        }
+    }
+
+    #[test]
+    fn test_wal_compact_to() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(
+            &mut rng,
+            0..=19,
+            BlockRangeParams { tx_count: 0..1, ..Default::default() },
+        )
+        .into_iter()
+        .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+        .collect::<eyre::Result<Vec<_>>>()?;
+        let notifications = blocks
+            .iter()
+            .map(|block| ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(vec![block.clone()], Default::default(), None)),
+            })
+            .collect::<Vec<_>>();
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+        for notification in &notifications {
+            wal.commit(notification)?;
+        }
+
+        wal.compact_to(blocks[9].num_hash())?;
+        assert_eq!(wal.inner.storage.files_range()?, Some(0..=9));
+        assert_eq!(read_notifications(&wal)?, notifications[10..]);
+        assert_eq!(wal.earliest_block(), Some(blocks[10].num_hash()));
+        assert_eq!(
+            wal.inner.block_cache.get_file_id_by_committed_block_hash(&blocks[10].hash()),
+            Some(0)
+        );
+
+        // Finalizing the notification with file ID 0 shifts the remaining ones by one
+        wal.compact_to(blocks[10].num_hash())?;
+        assert_eq!(wal.inner.storage.files_range()?, Some(0..=8));
+        assert_eq!(read_notifications(&wal)?, notifications[11..]);
+
+        // New notifications are appended after the compacted ones
+        wal.commit(&notifications[0])?;
+        assert_eq!(wal.inner.storage.files_range()?, Some(0..=9));
+
+        // The compacted WAL is read back after reopening
+        drop(wal);
+        let wal = Wal::new(&temp_dir)?;
+        let mut expected = notifications[11..].to_vec();
+        expected.push(notifications[0].clone());
+        assert_eq!(read_notifications(&wal)?, expected);
+        assert_eq!(wal.latest_block(), Some(blocks[0].num_hash()));
+
+        // Other storages are compacted the same way
+        let temp_dir = tempfile::tempdir()?;
+        for mut wal in [Wal::new_in_memory(), Wal::new_with_layout(&temp_dir, WalLayout::Sharded)?]
+        {
+            for notification in &notifications {
+                wal.commit(notification)?;
+            }
+            wal.compact_to(blocks[9].num_hash())?;
+            assert_eq!(wal.inner.storage.files_range()?, Some(0..=9));
+            assert_eq!(read_notifications(&wal)?, notifications[10..]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_gc() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let notifications = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| {
+                Ok(ExExNotification::ChainCommitted {
+                    new: Arc::new(Chain::new(
+                        vec![block.seal_with_senders().ok_or_eyre("failed to recover senders")?],
+                        Default::default(),
+                        None,
+                    )),
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?;
+        for notification in &notifications[..2] {
+            wal.commit(notification)?;
+        }
+        assert_eq!(wal.gc()?, Vec::<u64>::new());
+
+        // Write orphaned notifications before and after the cached ones, bypassing the block cache
+        wal.inner.storage.write_notification(5, &notifications[2], true)?;
+        wal.finalize(notifications[0].committed_chain().unwrap().tip().num_hash())?;
+        wal.inner.storage.write_notification(0, &notifications[0], true)?;
+        assert_eq!(wal.files_count()?, 3);
+
+        assert_eq!(wal.gc()?, vec![0, 5]);
+        assert_eq!(wal.files_count()?, 1);
+        assert_eq!(read_notifications(&wal)?, vec![notifications[1].clone()]);
+
+        // The WAL is still consistent after reopening
+        drop(wal);
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(read_notifications(&wal)?, vec![notifications[1].clone()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_flush() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=1, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(blocks, Default::default(), None)),
+        };
+
+        // Flushing syncs the storage
+        let syncs = Arc::new(AtomicUsize::new(0));
+        let storage = SyncCountingStorage { inner: InMemoryStorage::new(), syncs: syncs.clone() };
+        let mut wal =
+            Wal::from_inner(WalInner::new(Box::new(storage))?).with_durability(WalDurability::None);
+        wal.commit(&notification)?;
+        assert_eq!(syncs.load(Ordering::Relaxed), 0);
+        wal.flush()?;
+        assert_eq!(syncs.load(Ordering::Relaxed), 1);
+        wal.close()?;
+        assert_eq!(syncs.load(Ordering::Relaxed), 2);
+
+        // Flushed notifications are read back after reopening the WAL
+        let temp_dir = tempfile::tempdir()?;
+        let mut wal = Wal::new(&temp_dir)?.with_durability(WalDurability::None);
+        wal.commit(&notification)?;
+        wal.close()?;
+
+        let wal = Wal::new(&temp_dir)?;
+        assert_eq!(read_notifications(&wal)?, vec![notification]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_durability() -> eyre::Result<()> {
+        reth_tracing::init_test_tracing();
+
+        let mut rng = generators::rng();
+
+        let notifications = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| {
+                Ok(ExExNotification::ChainCommitted {
+                    new: Arc::new(Chain::new(
+                        vec![block.seal_with_senders().ok_or_eyre("failed to recover senders")?],
+                        Default::default(),
+                        None,
+                    )),
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        for (durability, syncs_per_commit) in [
+            (WalDurability::None, 0),
+            (WalDurability::PerEntry, 1),
+            (WalDurability::Periodic(Duration::ZERO), 1),
+            (WalDurability::Periodic(Duration::from_secs(3600)), 0),
+        ] {
+            let syncs = Arc::new(AtomicUsize::new(0));
+            let storage =
+                SyncCountingStorage { inner: InMemoryStorage::new(), syncs: syncs.clone() };
+            let mut wal =
+                Wal::from_inner(WalInner::new(Box::new(storage))?).with_durability(durability);
+
+            for (i, notification) in notifications.iter().enumerate() {
+                wal.commit(notification)?;
+                assert_eq!(
+                    syncs.load(Ordering::Relaxed),
+                    (i + 1) * syncs_per_commit,
+                    "unexpected number of syncs for {durability:?}"
+                );
+            }
+        }
 
         Ok(())
     }
@@ -1,42 +1,709 @@
 use std::{
-    fs::File,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug},
+    fs::{File, OpenOptions},
+    io::Write,
     ops::RangeInclusive,
     path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
-use eyre::OptionExt;
+use parking_lot::{Mutex, RwLock};
 use reth_exex_types::ExExNotification;
-use reth_tracing::tracing::debug;
+use reth_fs_util::FsPathError;
+use reth_provider::Chain;
+use reth_tracing::tracing::{debug, warn};
+use serde::{
+    de::{
+        self, value::MapAccessDeserializer, DeserializeSeed, IgnoredAny, IntoDeserializer,
+        MapAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
+};
 use tracing::instrument;
 
-/// The underlying WAL storage backed by a directory of files.
+use super::{WalError, WalResult};
+
+/// The underlying WAL storage.
+///
+/// Each notification is stored under a unique file ID. File IDs are expected to be monotonically
+/// increasing in the order of the notifications.
+pub trait Storage: Debug + Send + Sync {
+    /// Returns the range of file IDs in the storage.
+    ///
+    /// If there are no files in the storage, returns `None`.
+    fn files_range(&self) -> WalResult<Option<RangeInclusive<u64>>>;
+
+    /// Returns the file IDs of all notifications in the storage, in ascending order.
+    fn file_ids(&self) -> WalResult<Vec<u64>> {
+        let Some(range) = self.files_range()? else { return Ok(Vec::new()) };
+
+        let mut file_ids = Vec::new();
+        for file_id in range {
+            if self.read_notification(file_id)?.is_some() {
+                file_ids.push(file_id);
+            }
+        }
+        Ok(file_ids)
+    }
+
+    /// Returns the path to the directory the notifications are stored in, or `None` if the
+    /// storage is not backed by a directory.
+    fn directory(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Returns `true` if the storage can't be modified.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Refreshes the state cached by the storage, making the changes made to the underlying
+    /// storage outside of this instance visible.
+    fn refresh(&self) -> WalResult<()> {
+        Ok(())
+    }
+
+    /// Removes notifications from the storage according to the given range.
+    ///
+    /// # Returns
+    ///
+    /// Number of removed notifications.
+    fn remove_notifications(&self, range: RangeInclusive<u64>) -> WalResult<usize>;
+
+    /// Removes all notifications from the storage.
+    ///
+    /// After a crash, the storage contains either all notifications or none of them.
+    ///
+    /// # Returns
+    ///
+    /// Number of removed notifications.
+    fn clear(&self) -> WalResult<usize> {
+        let Some(range) = self.files_range()? else { return Ok(0) };
+        self.remove_notifications(range)
+    }
+
+    /// Rewrites the notifications with the given file IDs, in ascending order, under consecutive
+    /// file IDs starting at `0`, keeping their order. All other notifications are removed.
+    ///
+    /// After a crash, the storage contains either the notifications from before the compaction,
+    /// or the compacted ones.
+    fn compact(&self, file_ids: &[u64]) -> WalResult<()> {
+        // The new file ID is never higher than the old one, so moving the notifications in
+        // ascending order never overwrites a notification that wasn't moved yet.
+        for (new_file_id, file_id) in (0..).zip(file_ids.iter().copied()) {
+            if new_file_id != file_id {
+                let notification =
+                    self.read_notification(file_id)?.ok_or(WalError::NotFound { file_id })?;
+                self.write_notification(new_file_id, &notification, false)?;
+            }
+        }
+        self.sync()?;
+
+        if let Some(range) = self.files_range()? {
+            let compacted_files_count = file_ids.len() as u64;
+            if *range.end() >= compacted_files_count {
+                self.remove_notifications(compacted_files_count..=*range.end())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the notification with the given file ID.
+    fn read_notification(&self, file_id: u64) -> WalResult<Option<ExExNotification>>;
+
+    /// Writes the notification with the given file ID.
+    ///
+    /// If `sync` is `true`, this notification and all notifications written before it are durably
+    /// persisted before returning. Otherwise, durability is deferred until the next synced write
+    /// or [`Storage::sync`] call.
+    ///
+    /// # Returns
+    ///
+    /// Number of bytes written to the underlying storage.
+    fn write_notification(
+        &self,
+        file_id: u64,
+        notification: &ExExNotification,
+        sync: bool,
+    ) -> WalResult<u64>;
+
+    /// Durably persists all notifications written so far.
+    fn sync(&self) -> WalResult<()>;
+
+    /// Reads the persisted block cache index, or `None` if there's no index.
+    fn read_block_cache_index(&self) -> WalResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Persists the block cache index, replacing the previous one and the updates appended to it.
+    ///
+    /// The index is not synced to the disk, as it's validated against the notifications in the
+    /// storage when loaded.
+    fn write_block_cache_index(&self, _data: &[u8]) -> WalResult<()> {
+        Ok(())
+    }
+
+    /// Reads the updates appended to the persisted block cache index with
+    /// [`Storage::append_block_cache_index_update`], or `None` if there are no updates.
+    fn read_block_cache_index_updates(&self) -> WalResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Appends the update to the persisted block cache index, until it's replaced with
+    /// [`Storage::write_block_cache_index`].
+    ///
+    /// As the index itself, the updates are not synced to the disk.
+    fn append_block_cache_index_update(&self, _data: &[u8]) -> WalResult<()> {
+        Ok(())
+    }
+
+    /// Returns the fingerprint of the notification with the given file ID, or `None` if there's
+    /// no such notification or the storage doesn't persist the block cache index.
+    ///
+    /// The fingerprints are stored in the block cache index, to detect the notifications that
+    /// were rewritten after the index was persisted.
+    fn file_fingerprint(&self, _file_id: u64) -> WalResult<Option<FileFingerprint>> {
+        Ok(None)
+    }
+
+    /// Returns an iterator over the notifications with file IDs in the given range.
+    fn iter_notifications(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Box<dyn Iterator<Item = WalResult<(u64, ExExNotification)>> + '_> {
+        Box::new(range.map(move |id| {
+            let notification =
+                self.read_notification(id)?.ok_or(WalError::NotFound { file_id: id })?;
+
+            Ok((id, notification))
+        }))
+    }
+}
+
+/// Name of the file used to lock the WAL directory.
+const LOCK_FILE_NAME: &str = ".lock";
+
+/// Name of the file storing the block cache index.
+const BLOCK_CACHE_INDEX_FILE_NAME: &str = "block_cache.index";
+
+/// Name of the temporary file the block cache index is written to before it's renamed.
+const BLOCK_CACHE_INDEX_TMP_FILE_NAME: &str = "block_cache.index.tmp";
+
+/// Name of the file storing the updates appended to the block cache index.
+const BLOCK_CACHE_INDEX_UPDATES_FILE_NAME: &str = "block_cache.updates";
+
+/// Name of the file marking the range of file IDs being removed by [`Storage::clear`].
+const CLEAR_MARKER_FILE_NAME: &str = "clear.marker";
+
+/// Name of the temporary file the clear marker is written to before it's renamed.
+const CLEAR_MARKER_TMP_FILE_NAME: &str = "clear.tmp";
+
+/// Name of the file marking the compaction of the notifications staged by [`Storage::compact`].
+const COMPACT_MARKER_FILE_NAME: &str = "compact.marker";
+
+/// Name of the temporary file the compaction marker is written to before it's renamed.
+const COMPACT_MARKER_TMP_FILE_NAME: &str = "compact.tmp";
+
+/// Name of the directory the notifications are staged in by [`Storage::compact`], under their
+/// new file IDs.
+const COMPACT_STAGING_DIRECTORY_NAME: &str = "compact.staging";
+
+/// Extension of the temporary files the notifications are written to before they're renamed.
+const TMP_FILE_EXTENSION: &str = "tmp";
+
+/// Names of the files in the WAL directory that are not notifications.
+const NON_NOTIFICATION_FILE_NAMES: [&str; 9] = [
+    LOCK_FILE_NAME,
+    BLOCK_CACHE_INDEX_FILE_NAME,
+    BLOCK_CACHE_INDEX_TMP_FILE_NAME,
+    BLOCK_CACHE_INDEX_UPDATES_FILE_NAME,
+    CLEAR_MARKER_FILE_NAME,
+    CLEAR_MARKER_TMP_FILE_NAME,
+    COMPACT_MARKER_FILE_NAME,
+    COMPACT_MARKER_TMP_FILE_NAME,
+    COMPACT_STAGING_DIRECTORY_NAME,
+];
+
+/// Version of the format of the notification files written by [`FileStorage`].
+///
+/// Must be bumped on any change to the serialized layout of [`ExExNotification`], with the read
+/// path in [`FileStorage::read_notification`] handling all known versions.
+const WAL_VERSION: u16 = 1;
+
+/// The versioned notification file written by [`FileStorage`].
+#[derive(Debug, Serialize)]
+struct WalEntryRef<'a> {
+    version: u16,
+    notification: &'a ExExNotification,
+}
+
+impl WalEntryRef<'_> {
+    /// Approximate size of a serialized block header, withdrawals and other per-block fields.
+    const ESTIMATED_BLOCK_SIZE: usize = 2_000;
+    /// Approximate size of a serialized transaction and its sender.
+    const ESTIMATED_TRANSACTION_SIZE: usize = 500;
+    /// Approximate size of a serialized receipt without its logs.
+    const ESTIMATED_RECEIPT_SIZE: usize = 100;
+    /// Approximate size of a serialized log without its topics and data.
+    const ESTIMATED_LOG_SIZE: usize = 100;
+    /// Approximate size of a serialized account in the bundle state, without its storage.
+    const ESTIMATED_ACCOUNT_SIZE: usize = 300;
+    /// Approximate size of a serialized storage slot in the bundle state.
+    const ESTIMATED_STORAGE_SLOT_SIZE: usize = 200;
+
+    /// Returns the approximate size of the serialized entry, used to pre-allocate the buffer it's
+    /// serialized into.
+    ///
+    /// The estimate is based on the number of blocks, transactions, receipts and logs, and the
+    /// size of the bundle state of the notification chains.
+    fn estimated_serialized_size(&self) -> usize {
+        [self.notification.reverted_chain(), self.notification.committed_chain()]
+            .into_iter()
+            .flatten()
+            .map(|chain| estimated_chain_size(&chain))
+            .sum()
+    }
+}
+
+/// Returns the approximate size of the serialized chain, see
+/// [`WalEntryRef::estimated_serialized_size`].
+fn estimated_chain_size(chain: &Chain) -> usize {
+    let blocks_size = chain
+        .blocks()
+        .values()
+        .map(|block| {
+            WalEntryRef::ESTIMATED_BLOCK_SIZE +
+                block.body.transactions.len() * WalEntryRef::ESTIMATED_TRANSACTION_SIZE
+        })
+        .sum::<usize>();
+
+    let receipts_size = chain
+        .execution_outcome()
+        .receipts()
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|receipt| {
+            WalEntryRef::ESTIMATED_RECEIPT_SIZE +
+                receipt
+                    .logs
+                    .iter()
+                    .map(|log| {
+                        // Topics and data are hex-encoded
+                        WalEntryRef::ESTIMATED_LOG_SIZE +
+                            log.data.topics().len() * (2 + 2 * 32 + 4) +
+                            2 * log.data.data.len()
+                    })
+                    .sum::<usize>()
+        })
+        .sum::<usize>();
+
+    let bundle_size = chain
+        .execution_outcome()
+        .bundle
+        .state
+        .values()
+        .map(|account| {
+            WalEntryRef::ESTIMATED_ACCOUNT_SIZE +
+                account.storage.len() * WalEntryRef::ESTIMATED_STORAGE_SLOT_SIZE
+        })
+        .sum::<usize>();
+
+    blocks_size + receipts_size + bundle_size
+}
+
+/// A notification file as read from disk.
+#[derive(Debug)]
+enum WalEntry {
+    /// The notification file of [`WAL_VERSION`].
+    Current(ExExNotification),
+    /// The notification file of a version that can't be decoded. The notification is skipped.
+    Unsupported(u16),
+    /// The notification file written before the format was versioned, containing the bare
+    /// notification.
+    Legacy(ExExNotification),
+}
+
+impl<'de> Deserialize<'de> for WalEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(WalEntryVisitor)
+    }
+}
+
+/// Decodes a [`WalEntry`] in a single pass, dispatching on the first key of the file.
+///
+/// Versioned files start with either the `version` or the `notification` key. Legacy files contain
+/// the externally tagged notification, so the first key is its variant name.
+struct WalEntryVisitor;
+
+impl<'de> Visitor<'de> for WalEntryVisitor {
+    type Value = WalEntry;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a versioned WAL entry or a notification")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut key: Option<String> = map.next_key()?;
+        if let Some(variant) = key.take_if(|key| key != "version" && key != "notification") {
+            let notification =
+                ExExNotification::deserialize(MapAccessDeserializer::new(PrefixedMapAccess {
+                    key: Some(variant),
+                    map,
+                }))?;
+            return Ok(WalEntry::Legacy(notification))
+        }
+
+        let mut version = None;
+        let mut notification = None;
+        while let Some(field) = key {
+            match field.as_str() {
+                "version" => version = Some(map.next_value::<u16>()?),
+                // The notification of an unsupported version is skipped without decoding
+                "notification" if version.is_some_and(|version| version != WAL_VERSION) => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+                "notification" => notification = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+            key = map.next_key()?;
+        }
+
+        match (version, notification) {
+            (None, _) => Err(de::Error::missing_field("version")),
+            (Some(WAL_VERSION), Some(notification)) => Ok(WalEntry::Current(notification)),
+            (Some(WAL_VERSION), None) => Err(de::Error::missing_field("notification")),
+            (Some(version), _) => Ok(WalEntry::Unsupported(version)),
+        }
+    }
+}
+
+/// A [`MapAccess`] that yields an already consumed key before the rest of the map.
+struct PrefixedMapAccess<A> {
+    key: Option<String>,
+    map: A,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for PrefixedMapAccess<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.key.take() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
+
+/// Length and modification time of a notification file, see [`Storage::file_fingerprint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    /// Length of the file in bytes.
+    pub len: u64,
+    /// Modification time of the file, in nanoseconds since the Unix epoch.
+    pub modified: u128,
+}
+
+/// Number of notification files in a shard directory of [`WalLayout::Sharded`].
+const FILES_PER_SHARD: u64 = 1000;
+
+/// Layout of the notification files in the directory of [`FileStorage`].
+///
+/// The layout is not detected, a directory must always be opened with the layout it was created
+/// with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WalLayout {
+    /// All notification files are stored in the WAL directory.
+    #[default]
+    Flat,
+    /// Notification files are stored in subdirectories of the WAL directory, named after the file
+    /// ID divided by 1000, e.g. `2/2500.wal`. Keeps the directories small, which speeds up
+    /// listing them on some filesystems.
+    Sharded,
+}
+
+/// The WAL storage backed by a directory of files.
 ///
 /// Each notification is represented by a single file that contains a MessagePack-encoded
 /// notification.
-#[derive(Debug, Clone)]
-pub struct Storage {
+///
+/// The directory is protected by an advisory lock for the lifetime of the storage, so that it
+/// can't be opened for writing by more than one instance at a time.
+#[derive(Debug)]
+pub struct FileStorage {
     /// The path to the WAL file.
     path: PathBuf,
+    /// File IDs of the notifications that were written without `fsync`.
+    unsynced_file_ids: Mutex<Vec<u64>>,
+    /// Whether the storage was opened in read-only mode.
+    read_only: bool,
+    /// Layout of the notification files in the directory.
+    layout: WalLayout,
+    /// The cached range of file IDs in the directory, or `None` if the directory needs to be
+    /// scanned. Updated on writes and invalidated on removals.
+    files_range: Mutex<Option<Option<RangeInclusive<u64>>>>,
+    /// The lock held on the WAL directory.
+    _lock: DirectoryLock,
 }
 
-impl Storage {
-    /// Creates a new instance of [`Storage`] backed by the file at the given path and creates
+impl FileStorage {
+    /// Creates a new instance of [`FileStorage`] backed by the file at the given path and creates
     /// it doesn't exist.
-    pub(super) fn new(path: impl AsRef<Path>) -> eyre::Result<Self> {
+    ///
+    /// Takes an exclusive lock on the directory, returning an error if it is already opened by
+    /// another instance. The directory is only locked on Unix, see [`DirectoryLock`].
+    pub(super) fn new(path: impl AsRef<Path>) -> WalResult<Self> {
+        Self::open(path, false, WalLayout::default())
+    }
+
+    /// Same as [`FileStorage::new`], but with the given layout of the notification files.
+    pub(super) fn new_with_layout(path: impl AsRef<Path>, layout: WalLayout) -> WalResult<Self> {
+        Self::open(path, false, layout)
+    }
+
+    /// Creates a new read-only instance of [`FileStorage`] backed by the file at the given path
+    /// and creates it doesn't exist.
+    ///
+    /// Takes a shared lock on the directory, so multiple read-only instances can be opened at the
+    /// same time, but not alongside a read-write one.
+    pub(super) fn new_read_only(path: impl AsRef<Path>, layout: WalLayout) -> WalResult<Self> {
+        Self::open(path, true, layout)
+    }
+
+    fn open(path: impl AsRef<Path>, read_only: bool, layout: WalLayout) -> WalResult<Self> {
         reth_fs_util::create_dir_all(&path)?;
+        let lock = DirectoryLock::acquire(path.as_ref(), !read_only)?;
 
-        Ok(Self { path: path.as_ref().to_path_buf() })
+        let storage = Self {
+            path: path.as_ref().to_path_buf(),
+            unsynced_file_ids: Mutex::new(Vec::new()),
+            read_only,
+            layout,
+            files_range: Mutex::new(None),
+            _lock: lock,
+        };
+        // A read-only instance can't remove the notifications, it's completed by the next
+        // read-write one
+        if !read_only {
+            storage.complete_interrupted_clear()?;
+            storage.complete_interrupted_compaction()?;
+            storage.remove_tmp_files()?;
+        }
+
+        Ok(storage)
+    }
+
+    /// Removes the temporary notification files left by the writes that were interrupted before
+    /// the file was renamed. The notifications in them were never committed.
+    fn remove_tmp_files(&self) -> WalResult<()> {
+        let mut directories = vec![self.path.clone()];
+        while let Some(directory) = directories.pop() {
+            for entry in reth_fs_util::read_dir(&directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_name() == COMPACT_STAGING_DIRECTORY_NAME {
+                    continue
+                }
+                if self.layout == WalLayout::Sharded && directory == self.path {
+                    if entry.file_type()?.is_dir() {
+                        directories.push(path);
+                    }
+                    continue
+                }
+                if Self::is_tmp_notification_file(&path) {
+                    warn!(?path, "Removing the temporary notification file from WAL");
+                    reth_fs_util::remove_file(&path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the path is a temporary file a notification is written to, see
+    /// [`Storage::write_notification`].
+    fn is_tmp_notification_file(path: &Path) -> bool {
+        path.extension().is_some_and(|extension| extension == TMP_FILE_EXTENSION) &&
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.parse::<u64>().is_ok())
+    }
+
+    /// Writes the marker with the range of file IDs that are being removed by
+    /// [`Storage::clear`]. Once the marker is written, the notifications are considered removed.
+    fn write_clear_marker(&self, range: &RangeInclusive<u64>) -> WalResult<()> {
+        let data = serde_json::to_vec(range).map_err(WalError::Encode)?;
+        reth_fs_util::atomic_write_file(&self.path.join(CLEAR_MARKER_FILE_NAME), |file| {
+            file.write_all(&data)
+        })?;
+        Ok(())
+    }
+
+    /// Removes the notifications in the range of the clear marker, and then the marker itself.
+    fn remove_cleared_notifications(&self, range: RangeInclusive<u64>) -> WalResult<usize> {
+        for id in range.clone() {
+            self.remove_notification(id);
+        }
+        self.remove_empty_shards(&range);
+        *self.files_range.lock() = None;
+        reth_fs_util::remove_file(self.path.join(CLEAR_MARKER_FILE_NAME))?;
+
+        Ok(range.count())
+    }
+
+    /// Completes the [`Storage::clear`] that was interrupted by a crash, if any.
+    #[instrument(target = "exex::wal::storage", skip(self))]
+    fn complete_interrupted_clear(&self) -> WalResult<()> {
+        let marker_path = self.path.join(CLEAR_MARKER_FILE_NAME);
+        let data = match std::fs::read(&marker_path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(FsPathError::read(err, &marker_path).into()),
+        };
+        let range: RangeInclusive<u64> = serde_json::from_slice(&data).map_err(WalError::Decode)?;
+
+        let removed_notifications = self.remove_cleared_notifications(range)?;
+        debug!(?removed_notifications, "Completed interrupted clear of the storage");
+
+        Ok(())
+    }
+
+    /// Writes the marker of the compaction of the notifications staged in
+    /// [`COMPACT_STAGING_DIRECTORY_NAME`]. Once the marker is written, the notifications are
+    /// considered compacted.
+    fn write_compact_marker(&self, marker: &CompactMarker) -> WalResult<()> {
+        let data = serde_json::to_vec(marker).map_err(WalError::Encode)?;
+        reth_fs_util::atomic_write_file(&self.path.join(COMPACT_MARKER_FILE_NAME), |file| {
+            file.write_all(&data)
+        })?;
+        Ok(())
+    }
+
+    /// Replaces the notifications in the removed range of the compaction marker with the staged
+    /// ones, and then removes the staging directory and the marker.
+    ///
+    /// Can be run again if interrupted: a notification in the removed range is only removed if
+    /// it's not replaced by a staged one yet, so the already moved notifications are preserved.
+    fn move_compacted_notifications(&self, marker: CompactMarker) -> WalResult<()> {
+        let staging_path = self.path.join(COMPACT_STAGING_DIRECTORY_NAME);
+        let staged_file_path = |file_id: u64| staging_path.join(format!("{file_id}.wal"));
+
+        for file_id in marker.removed.clone() {
+            if file_id >= marker.files_count || staged_file_path(file_id).exists() {
+                self.remove_notification(file_id);
+            }
+        }
+
+        let mut directories = BTreeSet::from([self.path.clone()]);
+        for file_id in 0..marker.files_count {
+            let staged_file_path = staged_file_path(file_id);
+            if !staged_file_path.exists() {
+                continue
+            }
+
+            let file_path = self.file_path(file_id);
+            if let Some(directory) = file_path.parent() {
+                reth_fs_util::create_dir_all(directory)?;
+                directories.insert(directory.to_path_buf());
+            }
+            reth_fs_util::rename(&staged_file_path, &file_path)?;
+        }
+
+        // Persist the renames of the files
+        for directory in directories {
+            File::open(&directory)
+                .map_err(|err| FsPathError::open(err, &directory))?
+                .sync_all()
+                .map_err(|err| FsPathError::fsync(err, &directory))?;
+        }
+
+        self.remove_empty_shards(&marker.removed);
+        self.unsynced_file_ids.lock().clear();
+        *self.files_range.lock() = None;
+        reth_fs_util::remove_dir_all(&staging_path)?;
+        reth_fs_util::remove_file(self.path.join(COMPACT_MARKER_FILE_NAME))?;
+
+        Ok(())
+    }
+
+    /// Completes the [`Storage::compact`] that was interrupted by a crash after the compaction
+    /// marker was written, or discards the staged notifications if it was interrupted before.
+    #[instrument(target = "exex::wal::storage", skip(self))]
+    fn complete_interrupted_compaction(&self) -> WalResult<()> {
+        let marker_path = self.path.join(COMPACT_MARKER_FILE_NAME);
+        let data = match std::fs::read(&marker_path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let staging_path = self.path.join(COMPACT_STAGING_DIRECTORY_NAME);
+                if staging_path.exists() {
+                    warn!("Discarding the notifications staged by an interrupted compaction");
+                    reth_fs_util::remove_dir_all(&staging_path)?;
+                }
+                return Ok(())
+            }
+            Err(err) => return Err(FsPathError::read(err, &marker_path).into()),
+        };
+        let marker: CompactMarker = serde_json::from_slice(&data).map_err(WalError::Decode)?;
+
+        let files_count = marker.files_count;
+        self.move_compacted_notifications(marker)?;
+        debug!(?files_count, "Completed interrupted compaction of the storage");
+
+        Ok(())
+    }
+
+    fn ensure_writable(&self) -> WalResult<()> {
+        if self.read_only {
+            return Err(WalError::ReadOnly(self.path.clone()))
+        }
+
+        Ok(())
     }
 
     fn file_path(&self, id: u64) -> PathBuf {
-        self.path.join(format!("{id}.wal"))
+        match self.layout {
+            WalLayout::Flat => self.path.join(format!("{id}.wal")),
+            WalLayout::Sharded => self.shard_path(id / FILES_PER_SHARD).join(format!("{id}.wal")),
+        }
+    }
+
+    fn shard_path(&self, shard: u64) -> PathBuf {
+        self.path.join(shard.to_string())
     }
 
-    fn parse_filename(filename: &str) -> eyre::Result<u64> {
+    /// Removes the shard directories of the given range of file IDs that have no files left.
+    fn remove_empty_shards(&self, range: &RangeInclusive<u64>) {
+        if self.layout != WalLayout::Sharded {
+            return
+        }
+
+        for shard in range.start() / FILES_PER_SHARD..=range.end() / FILES_PER_SHARD {
+            // Fails if the shard still has files, which is expected
+            if std::fs::remove_dir(self.shard_path(shard)).is_ok() {
+                debug!(shard, "Removed empty shard directory");
+            }
+        }
+    }
+
+    fn parse_filename(filename: &str) -> WalResult<u64> {
         filename
             .strip_suffix(".wal")
             .and_then(|s| s.parse().ok())
-            .ok_or_eyre(format!("failed to parse file name: {filename}"))
+            .ok_or_else(|| WalError::InvalidFileName(filename.to_string()))
     }
 
     /// Removes notification for the given file ID from the storage.
@@ -48,79 +715,453 @@ impl Storage {
         }
     }
 
-    /// Returns the range of file IDs in the storage.
-    ///
-    /// If there are no files in the storage, returns `None`.
-    pub(super) fn files_range(&self) -> eyre::Result<Option<RangeInclusive<u64>>> {
-        let mut min_id = None;
-        let mut max_id = None;
+    /// Scans the directory for the range of file IDs.
+    fn scan_files_range(&self) -> WalResult<Option<RangeInclusive<u64>>> {
+        let file_ids = self.scan_file_ids()?;
+        Ok(file_ids.first().zip(file_ids.last()).map(|(min_id, max_id)| *min_id..=*max_id))
+    }
+
+    /// Scans the directory for the file IDs, in ascending order.
+    #[instrument(target = "exex::wal::storage", skip(self))]
+    fn scan_file_ids(&self) -> WalResult<Vec<u64>> {
+        debug!("Scanning WAL directory");
 
-        for entry in reth_fs_util::read_dir(&self.path)? {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            let file_id = Self::parse_filename(&file_name.to_string_lossy())?;
+        let mut file_ids = Vec::new();
 
-            min_id = min_id.map_or(Some(file_id), |min_id: u64| Some(min_id.min(file_id)));
-            max_id = max_id.map_or(Some(file_id), |max_id: u64| Some(max_id.max(file_id)));
+        let mut directories = vec![self.path.clone()];
+        while let Some(directory) = directories.pop() {
+            for entry in reth_fs_util::read_dir(&directory)? {
+                let entry = entry?;
+                let file_name = entry.file_name();
+                if NON_NOTIFICATION_FILE_NAMES.iter().any(|name| file_name == *name) {
+                    continue
+                }
+                // Removed by the next read-write instance, see `remove_tmp_files`
+                if Self::is_tmp_notification_file(&entry.path()) {
+                    continue
+                }
+                if self.layout == WalLayout::Sharded && directory == self.path {
+                    if !entry.file_type()?.is_dir() {
+                        return Err(WalError::InvalidFileName(
+                            file_name.to_string_lossy().into_owned(),
+                        ))
+                    }
+                    directories.push(entry.path());
+                    continue
+                }
+                file_ids.push(Self::parse_filename(&file_name.to_string_lossy())?);
+            }
         }
 
-        Ok(min_id.zip(max_id).map(|(min_id, max_id)| min_id..=max_id))
+        file_ids.sort_unstable();
+        Ok(file_ids)
     }
+}
+
+impl Storage for FileStorage {
+    fn directory(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn files_range(&self) -> WalResult<Option<RangeInclusive<u64>>> {
+        let mut files_range = self.files_range.lock();
+        if let Some(files_range) = files_range.as_ref() {
+            return Ok(files_range.clone())
+        }
+
+        let range = self.scan_files_range()?;
+        *files_range = Some(range.clone());
+        Ok(range)
+    }
+
+    fn file_ids(&self) -> WalResult<Vec<u64>> {
+        self.scan_file_ids()
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn refresh(&self) -> WalResult<()> {
+        let range = self.scan_files_range()?;
+        *self.files_range.lock() = Some(range);
+        Ok(())
+    }
+
+    fn remove_notifications(&self, range: RangeInclusive<u64>) -> WalResult<usize> {
+        self.ensure_writable()?;
 
-    /// Removes notifications from the storage according to the given range.
-    ///
-    /// # Returns
-    ///
-    /// Number of removed notifications.
-    pub(super) fn remove_notifications(&self, range: RangeInclusive<u64>) -> eyre::Result<usize> {
         for id in range.clone() {
             self.remove_notification(id);
         }
+        self.remove_empty_shards(&range);
+        *self.files_range.lock() = None;
 
         Ok(range.count())
     }
 
-    pub(super) fn iter_notifications(
-        &self,
-        range: RangeInclusive<u64>,
-    ) -> impl Iterator<Item = eyre::Result<(u64, ExExNotification)>> + '_ {
-        range.map(move |id| {
-            let notification = self.read_notification(id)?.ok_or_eyre("notification not found")?;
+    #[instrument(target = "exex::wal::storage", skip(self))]
+    fn clear(&self) -> WalResult<usize> {
+        self.ensure_writable()?;
 
-            Ok((id, notification))
-        })
+        let Some(range) = self.files_range()? else { return Ok(0) };
+        self.write_clear_marker(&range)?;
+        self.unsynced_file_ids.lock().clear();
+
+        self.remove_cleared_notifications(range)
     }
 
-    /// Reads the notification from the file with the given id.
     #[instrument(target = "exex::wal::storage", skip(self))]
-    pub(super) fn read_notification(&self, file_id: u64) -> eyre::Result<Option<ExExNotification>> {
+    fn compact(&self, file_ids: &[u64]) -> WalResult<()> {
+        self.ensure_writable()?;
+
+        let Some(removed) = self.files_range()? else { return Ok(()) };
+
+        // Stage the notification files under their new file IDs. They're copied as is, because
+        // the file ID is not a part of the encoded notification.
+        let staging_path = self.path.join(COMPACT_STAGING_DIRECTORY_NAME);
+        if staging_path.exists() {
+            reth_fs_util::remove_dir_all(&staging_path)?;
+        }
+        reth_fs_util::create_dir_all(&staging_path)?;
+        for (new_file_id, file_id) in (0..).zip(file_ids.iter().copied()) {
+            let file_path = self.file_path(file_id);
+            let staged_file_path = staging_path.join(format!("{new_file_id}.wal"));
+            std::fs::copy(&file_path, &staged_file_path)
+                .map_err(|err| FsPathError::read(err, &file_path))?;
+            File::open(&staged_file_path)
+                .and_then(|file| file.sync_all())
+                .map_err(|err| FsPathError::fsync(err, &staged_file_path))?;
+        }
+        File::open(&staging_path)
+            .and_then(|directory| directory.sync_all())
+            .map_err(|err| FsPathError::fsync(err, &staging_path))?;
+
+        // Commit the compaction, and then move the staged notifications into place
+        let marker = CompactMarker { removed, files_count: file_ids.len() as u64 };
+        self.write_compact_marker(&marker)?;
+        self.move_compacted_notifications(marker)
+    }
+
+    #[instrument(target = "exex::wal::storage", skip(self))]
+    fn read_notification(&self, file_id: u64) -> WalResult<Option<ExExNotification>> {
         let file_path = self.file_path(file_id);
         debug!(?file_path, "Reading notification from WAL");
 
-        let mut file = match File::open(&file_path) {
-            Ok(file) => file,
+        let data = match std::fs::read(&file_path) {
+            Ok(data) => data,
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
-            Err(err) => return Err(err.into()),
+            Err(err) => return Err(FsPathError::read(err, &file_path).into()),
         };
 
         // TODO(alexey): use rmp-serde when Alloy and Reth serde issues are resolved
-        Ok(serde_json::from_reader(&mut file)?)
+        let entry = serde_json::from_slice(&data)
+            .map_err(|source| WalError::Corrupt { file_id, source })?;
+        match entry {
+            WalEntry::Current(notification) | WalEntry::Legacy(notification) => {
+                Ok(Some(notification))
+            }
+            WalEntry::Unsupported(version) => Err(WalError::VersionUnsupported(version.into())),
+        }
     }
 
-    /// Writes the notification to the file with the given id.
     #[instrument(target = "exex::wal::storage", skip(self, notification))]
-    pub(super) fn write_notification(
+    fn write_notification(
         &self,
         file_id: u64,
         notification: &ExExNotification,
-    ) -> eyre::Result<()> {
+        sync: bool,
+    ) -> WalResult<u64> {
+        self.ensure_writable()?;
+
         let file_path = self.file_path(file_id);
-        debug!(?file_path, "Writing notification to WAL");
+        debug!(?file_path, ?sync, "Writing notification to WAL");
+        if self.layout == WalLayout::Sharded {
+            if let Some(shard_path) = file_path.parent() {
+                reth_fs_util::create_dir_all(shard_path)?;
+            }
+        }
+
+        // TODO(alexey): use rmp-serde when Alloy and Reth serde issues are resolved
+        let entry = WalEntryRef { version: WAL_VERSION, notification };
+        let mut data = Vec::with_capacity(entry.estimated_serialized_size());
+        serde_json::to_writer(&mut data, &entry).map_err(WalError::Encode)?;
+
+        if sync {
+            // Persist the notifications that were written without `fsync` before, so that this
+            // notification never becomes durable ahead of them.
+            self.sync()?;
+
+            reth_fs_util::atomic_write_file(&file_path, |file| file.write_all(&data))?;
+        } else {
+            let mut tmp_file_path = file_path.clone();
+            tmp_file_path.set_extension(TMP_FILE_EXTENSION);
+
+            reth_fs_util::write(&tmp_file_path, &data)?;
+            reth_fs_util::rename(&tmp_file_path, &file_path)?;
+
+            self.unsynced_file_ids.lock().push(file_id);
+        }
+
+        // Extend the cached range with the written file ID, if it's loaded
+        if let Some(files_range) = self.files_range.lock().as_mut() {
+            *files_range = Some(files_range.as_ref().map_or(file_id..=file_id, |range| {
+                *range.start().min(&file_id)..=*range.end().max(&file_id)
+            }));
+        }
+
+        Ok(data.len() as u64)
+    }
+
+    #[instrument(target = "exex::wal::storage", skip(self))]
+    fn sync(&self) -> WalResult<()> {
+        let mut unsynced_file_ids = self.unsynced_file_ids.lock();
+        if unsynced_file_ids.is_empty() {
+            return Ok(())
+        }
+
+        // The WAL directory is always synced, to persist the creation of the shard directories
+        let mut directories = BTreeSet::from([self.path.clone()]);
+        for file_id in unsynced_file_ids.iter() {
+            let file_path = self.file_path(*file_id);
+            match File::open(&file_path) {
+                Ok(file) => {
+                    file.sync_all().map_err(|err| FsPathError::fsync(err, &file_path))?;
+                }
+                // The notification was already removed from the storage
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(FsPathError::open(err, &file_path).into()),
+            }
+            if let Some(directory) = file_path.parent() {
+                directories.insert(directory.to_path_buf());
+            }
+        }
+
+        // Persist the renames of the files
+        for directory in directories {
+            File::open(&directory)
+                .map_err(|err| FsPathError::open(err, &directory))?
+                .sync_all()
+                .map_err(|err| FsPathError::fsync(err, &directory))?;
+        }
+
+        debug!(synced_files = ?unsynced_file_ids.len(), "Synced notifications to disk");
+        unsynced_file_ids.clear();
+
+        Ok(())
+    }
+
+    fn read_block_cache_index(&self) -> WalResult<Option<Vec<u8>>> {
+        let file_path = self.path.join(BLOCK_CACHE_INDEX_FILE_NAME);
+        match std::fs::read(&file_path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(FsPathError::read(err, &file_path).into()),
+        }
+    }
+
+    fn file_fingerprint(&self, file_id: u64) -> WalResult<Option<FileFingerprint>> {
+        let file_path = self.file_path(file_id);
+        let metadata = match std::fs::metadata(&file_path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(FsPathError::metadata(err, &file_path).into()),
+        };
+        let modified = metadata
+            .modified()
+            .map_err(|err| FsPathError::metadata(err, &file_path))?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+
+        Ok(Some(FileFingerprint { len: metadata.len(), modified }))
+    }
+
+    #[instrument(target = "exex::wal::storage", skip_all)]
+    fn write_block_cache_index(&self, data: &[u8]) -> WalResult<()> {
+        self.ensure_writable()?;
+
+        let tmp_file_path = self.path.join(BLOCK_CACHE_INDEX_TMP_FILE_NAME);
+        reth_fs_util::write(&tmp_file_path, data)?;
+        reth_fs_util::rename(&tmp_file_path, self.path.join(BLOCK_CACHE_INDEX_FILE_NAME))?;
+
+        // The updates are already included in the index. If the node crashes before they're
+        // removed, they're applied to the index again on load, which doesn't change it.
+        let updates_file_path = self.path.join(BLOCK_CACHE_INDEX_UPDATES_FILE_NAME);
+        match std::fs::remove_file(&updates_file_path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(FsPathError::remove_file(err, &updates_file_path).into()),
+        }
+
+        debug!(bytes_written = data.len(), "Wrote block cache index");
+
+        Ok(())
+    }
 
-        Ok(reth_fs_util::atomic_write_file(&file_path, |file| {
-            // TODO(alexey): use rmp-serde when Alloy and Reth serde issues are resolved
-            serde_json::to_writer(file, notification)
-        })?)
+    fn read_block_cache_index_updates(&self) -> WalResult<Option<Vec<u8>>> {
+        let file_path = self.path.join(BLOCK_CACHE_INDEX_UPDATES_FILE_NAME);
+        match std::fs::read(&file_path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(FsPathError::read(err, &file_path).into()),
+        }
+    }
+
+    #[instrument(target = "exex::wal::storage", skip_all)]
+    fn append_block_cache_index_update(&self, data: &[u8]) -> WalResult<()> {
+        self.ensure_writable()?;
+
+        let file_path = self.path.join(BLOCK_CACHE_INDEX_UPDATES_FILE_NAME);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)
+            .map_err(|err| FsPathError::open(err, &file_path))?
+            .write_all(data)
+            .map_err(|err| FsPathError::write(err, &file_path))?;
+
+        debug!(bytes_written = data.len(), "Appended block cache index update");
+
+        Ok(())
+    }
+}
+
+/// The marker of the compaction written by [`FileStorage::compact`].
+#[derive(Debug, Serialize, Deserialize)]
+struct CompactMarker {
+    /// The range of file IDs of the notifications replaced by the compacted ones.
+    removed: RangeInclusive<u64>,
+    /// The number of compacted notifications, staged under file IDs starting at `0`.
+    files_count: u64,
+}
+
+/// An advisory lock on a directory, released when dropped.
+///
+/// Locking is only supported on Unix. On other platforms, the lock file is created, but the
+/// directory isn't locked, so nothing prevents it from being opened by multiple instances.
+#[derive(Debug)]
+struct DirectoryLock {
+    /// The locked file. The lock is released when the file is closed.
+    _file: File,
+}
+
+impl DirectoryLock {
+    /// Acquires an exclusive or a shared lock on the given directory, returning an error if it's
+    /// already locked in a conflicting mode.
+    fn acquire(path: &Path, exclusive: bool) -> WalResult<Self> {
+        let file_path = path.join(LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&file_path)
+            .map_err(|err| FsPathError::open(err, &file_path))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+
+            let operation = if exclusive { libc::LOCK_EX } else { libc::LOCK_SH };
+            // SAFETY: the file descriptor is valid for the lifetime of `file`.
+            if unsafe { libc::flock(file.as_raw_fd(), operation | libc::LOCK_NB) } != 0 {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::WouldBlock {
+                    return Err(WalError::DirectoryLocked(path.to_path_buf()))
+                }
+                return Err(err.into())
+            }
+        }
+        #[cfg(not(unix))]
+        warn!(
+            ?path,
+            exclusive,
+            "Directory locking is not supported on this platform, the WAL may be opened by multiple instances"
+        );
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// The WAL storage backed by memory.
+///
+/// Notifications are kept in a map keyed by file ID and are lost when the storage is dropped.
+/// Useful for tests that don't need to persist the WAL on disk.
+#[derive(Debug, Default)]
+pub struct InMemoryStorage {
+    notifications: RwLock<BTreeMap<u64, ExExNotification>>,
+}
+
+impl InMemoryStorage {
+    /// Creates a new empty instance of [`InMemoryStorage`].
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn files_range(&self) -> WalResult<Option<RangeInclusive<u64>>> {
+        let notifications = self.notifications.read();
+        Ok(notifications
+            .first_key_value()
+            .zip(notifications.last_key_value())
+            .map(|((min_id, _), (max_id, _))| *min_id..=*max_id))
+    }
+
+    fn file_ids(&self) -> WalResult<Vec<u64>> {
+        Ok(self.notifications.read().keys().copied().collect())
+    }
+
+    fn remove_notifications(&self, range: RangeInclusive<u64>) -> WalResult<usize> {
+        let mut notifications = self.notifications.write();
+        for id in range.clone() {
+            notifications.remove(&id);
+        }
+
+        Ok(range.count())
+    }
+
+    fn clear(&self) -> WalResult<usize> {
+        let mut notifications = self.notifications.write();
+        let removed_notifications = notifications.len();
+        notifications.clear();
+
+        Ok(removed_notifications)
+    }
+
+    fn compact(&self, file_ids: &[u64]) -> WalResult<()> {
+        let mut notifications = self.notifications.write();
+        *notifications = (0..)
+            .zip(file_ids)
+            .map(|(new_file_id, file_id)| {
+                notifications
+                    .get(file_id)
+                    .map(|notification| (new_file_id, notification.clone()))
+                    .ok_or(WalError::NotFound { file_id: *file_id })
+            })
+            .collect::<WalResult<_>>()?;
+
+        Ok(())
+    }
+
+    fn read_notification(&self, file_id: u64) -> WalResult<Option<ExExNotification>> {
+        Ok(self.notifications.read().get(&file_id).cloned())
+    }
+
+    fn write_notification(
+        &self,
+        file_id: u64,
+        notification: &ExExNotification,
+        _sync: bool,
+    ) -> WalResult<u64> {
+        self.notifications.write().insert(file_id, notification.clone());
+        // Nothing is persisted
+        Ok(0)
+    }
+
+    fn sync(&self) -> WalResult<()> {
+        Ok(())
     }
 }
 
@@ -130,17 +1171,24 @@ mod tests {
 
     use eyre::OptionExt;
     use reth_exex_types::ExExNotification;
-    use reth_provider::Chain;
-    use reth_testing_utils::generators::{self, random_block};
+    use reth_primitives::Receipts;
+    use reth_provider::{Chain, ExecutionOutcome};
+    use reth_testing_utils::generators::{
+        self, random_block, random_block_range, random_receipt, BlockRangeParams,
+    };
 
-    use super::Storage;
+    use super::{
+        CompactMarker, FileStorage, Storage, WalEntryRef, WalLayout, COMPACT_MARKER_FILE_NAME,
+        COMPACT_STAGING_DIRECTORY_NAME, WAL_VERSION,
+    };
+    use crate::wal::WalError;
 
     #[test]
     fn test_roundtrip() -> eyre::Result<()> {
         let mut rng = generators::rng();
 
         let temp_dir = tempfile::tempdir()?;
-        let storage = Storage::new(&temp_dir)?;
+        let storage = FileStorage::new(&temp_dir)?;
 
         let old_block = random_block(&mut rng, 0, Default::default())
             .seal_with_senders()
@@ -156,10 +1204,314 @@ mod tests {
 
         // Do a round trip serialization and deserialization
         let file_id = 0;
-        storage.write_notification(file_id, &notification)?;
+        storage.write_notification(file_id, &notification, true)?;
         let deserialized_notification = storage.read_notification(file_id)?;
         assert_eq!(deserialized_notification, Some(notification));
 
         Ok(())
     }
+
+    #[test]
+    fn test_estimated_serialized_size() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        for (tx_count, logs_count) in [(0, 0), (10, 0), (10, 1), (50, 2)] {
+            let blocks = random_block_range(
+                &mut rng,
+                0..=4,
+                BlockRangeParams { tx_count: tx_count..tx_count + 1, ..Default::default() },
+            )
+            .into_iter()
+            .map(|block| block.seal_with_senders().ok_or_eyre("failed to recover senders"))
+            .collect::<eyre::Result<Vec<_>>>()?;
+            let receipts = blocks
+                .iter()
+                .map(|block| {
+                    block
+                        .body
+                        .transactions
+                        .iter()
+                        .map(|tx| Some(random_receipt(&mut rng, tx, Some(logs_count))))
+                        .collect()
+                })
+                .collect();
+            let execution_outcome = ExecutionOutcome {
+                receipts: Receipts { receipt_vec: receipts },
+                ..Default::default()
+            };
+            let notification = ExExNotification::ChainCommitted {
+                new: Arc::new(Chain::new(blocks, execution_outcome, None)),
+            };
+
+            let entry = WalEntryRef { version: WAL_VERSION, notification: &notification };
+            let estimated_size = entry.estimated_serialized_size();
+            let actual_size = serde_json::to_vec(&entry)?.len();
+            assert!(
+                actual_size / 2 <= estimated_size && estimated_size <= actual_size * 2,
+                "estimated {estimated_size} bytes, actual {actual_size} bytes for {tx_count} \
+                 transactions with {logs_count} logs each"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsynced_write() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let storage = FileStorage::new(&temp_dir)?;
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+
+        // Notification written without sync is readable right away and is synced later
+        let file_id = 0;
+        storage.write_notification(file_id, &notification, false)?;
+        assert_eq!(storage.unsynced_file_ids.lock().as_slice(), [file_id]);
+        assert_eq!(storage.read_notification(file_id)?, Some(notification.clone()));
+
+        storage.sync()?;
+        assert!(storage.unsynced_file_ids.lock().is_empty());
+
+        // Synced write also syncs the previously unsynced notifications
+        storage.write_notification(file_id + 1, &notification, false)?;
+        storage.write_notification(file_id + 2, &notification, true)?;
+        assert!(storage.unsynced_file_ids.lock().is_empty());
+        assert_eq!(storage.files_range()?, Some(0..=2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_files_range() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let storage = FileStorage::new(&temp_dir)?;
+        assert_eq!(storage.files_range()?, None);
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+
+        // Writes are reflected in the cached range
+        storage.write_notification(0, &notification, true)?;
+        storage.write_notification(1, &notification, false)?;
+        assert_eq!(storage.files_range()?, Some(0..=1));
+
+        // A file added outside of the storage is not visible, because the directory is not
+        // scanned again
+        std::fs::copy(storage.file_path(0), storage.file_path(5))?;
+        assert_eq!(storage.files_range()?, Some(0..=1));
+        storage.write_notification(2, &notification, true)?;
+        assert_eq!(storage.files_range()?, Some(0..=2));
+
+        // Until the storage is refreshed
+        storage.refresh()?;
+        assert_eq!(storage.files_range()?, Some(0..=5));
+
+        // Removals invalidate the cached range
+        storage.remove_notifications(0..=0)?;
+        assert_eq!(storage.files_range()?, Some(1..=5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_format() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let storage = FileStorage::new(&temp_dir)?;
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+
+        // The notification is written with the current version
+        storage.write_notification(0, &notification, true)?;
+        let entry: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(storage.file_path(0))?)?;
+        assert_eq!(entry["version"], 1);
+
+        // A version 1 file is read back
+        std::fs::write(
+            storage.file_path(1),
+            serde_json::to_vec(&serde_json::json!({ "version": 1, "notification": notification }))?,
+        )?;
+        assert_eq!(storage.read_notification(1)?, Some(notification.clone()));
+
+        // A file of a future version can't be read
+        std::fs::write(
+            storage.file_path(2),
+            serde_json::to_vec(&serde_json::json!({ "version": 2, "notification": notification }))?,
+        )?;
+        assert!(matches!(storage.read_notification(2), Err(WalError::VersionUnsupported(2))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_format() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let storage = FileStorage::new(&temp_dir)?;
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+
+        // A file written before the format was versioned contains the bare notification
+        std::fs::write(storage.file_path(0), serde_json::to_vec(&notification)?)?;
+        assert_eq!(storage.read_notification(0)?, Some(notification));
+
+        // A truncated legacy file is corrupt
+        std::fs::write(storage.file_path(1), b"{\"ChainCommitted\":{")?;
+        assert!(matches!(storage.read_notification(1), Err(WalError::Corrupt { file_id: 1, .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tmp_files() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let storage = FileStorage::new(&temp_dir)?;
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+        storage.write_notification(0, &notification, true)?;
+        drop(storage);
+
+        // The node crashed before the temporary file of the next notification was renamed
+        let tmp_file_path = temp_dir.path().join("1.tmp");
+        std::fs::write(&tmp_file_path, b"{\"version\":1,")?;
+
+        // A read-only instance ignores the temporary file
+        let storage = FileStorage::new_read_only(&temp_dir, WalLayout::default())?;
+        assert_eq!(storage.files_range()?, Some(0..=0));
+        assert!(tmp_file_path.exists());
+        drop(storage);
+
+        // A read-write instance removes it
+        let storage = FileStorage::new(&temp_dir)?;
+        assert!(!tmp_file_path.exists());
+        assert_eq!(storage.files_range()?, Some(0..=0));
+        assert_eq!(storage.read_notification(0)?, Some(notification));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_clear() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let temp_dir = tempfile::tempdir()?;
+        let storage = FileStorage::new(&temp_dir)?;
+
+        let block = random_block(&mut rng, 0, Default::default())
+            .seal_with_senders()
+            .ok_or_eyre("failed to recover senders")?;
+        let notification = ExExNotification::ChainCommitted {
+            new: Arc::new(Chain::new(vec![block], Default::default(), None)),
+        };
+        storage.write_notification(0, &notification, true)?;
+        storage.write_notification(1, &notification, true)?;
+
+        // The node crashed after the notifications were marked for removal
+        storage.write_clear_marker(&(0..=1))?;
+        drop(storage);
+
+        // The removal is completed on the next open
+        let storage = FileStorage::new(&temp_dir)?;
+        assert_eq!(storage.files_range()?, None);
+        assert!(!temp_dir.path().join(super::CLEAR_MARKER_FILE_NAME).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_compaction() -> eyre::Result<()> {
+        let mut rng = generators::rng();
+
+        let notifications = random_block_range(&mut rng, 0..=2, BlockRangeParams::default())
+            .into_iter()
+            .map(|block| {
+                Ok(ExExNotification::ChainCommitted {
+                    new: Arc::new(Chain::new(
+                        vec![block.seal_with_senders().ok_or_eyre("failed to recover senders")?],
+                        Default::default(),
+                        None,
+                    )),
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let file_ids = [2, 3, 5];
+
+        let temp_dir = tempfile::tempdir()?;
+        let staging_path = temp_dir.path().join(COMPACT_STAGING_DIRECTORY_NAME);
+        let storage = FileStorage::new(&temp_dir)?;
+        for (file_id, notification) in file_ids.iter().zip(&notifications) {
+            storage.write_notification(*file_id, notification, true)?;
+        }
+        let stage = |storage: &FileStorage| -> eyre::Result<()> {
+            reth_fs_util::create_dir_all(&staging_path)?;
+            for (new_file_id, file_id) in file_ids.iter().enumerate() {
+                std::fs::copy(
+                    storage.file_path(*file_id),
+                    staging_path.join(format!("{new_file_id}.wal")),
+                )?;
+            }
+            Ok(())
+        };
+
+        // The node crashed before the compaction was marked, so it's discarded on the next open
+        stage(&storage)?;
+        drop(storage);
+        let storage = FileStorage::new(&temp_dir)?;
+        assert!(!staging_path.exists());
+        assert_eq!(storage.file_ids()?, file_ids);
+        for (file_id, notification) in file_ids.iter().zip(&notifications) {
+            assert_eq!(storage.read_notification(*file_id)?.as_ref(), Some(notification));
+        }
+
+        // The node crashed after the compaction was marked, and the notification with file ID 5
+        // was moved to the file ID 2, replacing the old one
+        stage(&storage)?;
+        storage.write_compact_marker(&CompactMarker { removed: 2..=5, files_count: 3 })?;
+        std::fs::rename(staging_path.join("2.wal"), storage.file_path(2))?;
+        drop(storage);
+
+        // The compaction is completed on the next open
+        let storage = FileStorage::new(&temp_dir)?;
+        assert!(!staging_path.exists());
+        assert!(!temp_dir.path().join(COMPACT_MARKER_FILE_NAME).exists());
+        assert_eq!(storage.file_ids()?, [0, 1, 2]);
+        for (file_id, notification) in (0..).zip(&notifications) {
+            assert_eq!(storage.read_notification(file_id)?.as_ref(), Some(notification));
+        }
+
+        Ok(())
+    }
 }
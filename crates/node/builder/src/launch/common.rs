@@ -692,11 +692,14 @@ where
 
         let consensus: Arc<dyn Consensus> = Arc::new(components.consensus().clone());
 
-        let tree_externals = TreeExternals::new(
+        let mut tree_externals = TreeExternals::new(
             self.provider_factory().clone().with_prune_modes(self.prune_modes()),
             consensus.clone(),
             components.block_executor().clone(),
         );
+        if let Some(threshold) = self.node_config().debug.long_read_transaction_threshold {
+            tree_externals = tree_externals.with_instrumented_read_transactions(threshold);
+        }
         let tree = BlockchainTree::new(tree_externals, *self.tree_config())?
             .with_sync_metrics_tx(self.sync_metrics_tx())
             // Note: This is required because we need to ensure that both the components and the
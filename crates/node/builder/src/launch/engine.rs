@@ -335,60 +335,72 @@ where
         let chainspec = ctx.chain_spec();
         let (exit, rx) = oneshot::channel();
         info!(target: "reth::cli", "Starting consensus engine");
-        ctx.task_executor().spawn_critical("consensus engine", async move {
-            if let Some(initial_target) = initial_target {
-                debug!(target: "reth::cli", %initial_target,  "start backfill sync");
-                eth_service.orchestrator_mut().start_backfill_sync(initial_target);
-            }
-
-            let mut res = Ok(());
-
-            // advance the chain and await payloads built locally to add into the engine api tree handler to prevent re-execution if that block is received as payload from the CL
-            loop {
-                tokio::select! {
-                    payload = built_payloads.select_next_some() => {
-                        if let Some(executed_block) = payload.executed_block() {
-                            debug!(target: "reth::cli", hash=%executed_block.block().hash(),  "inserting built payload");
-                            eth_service.orchestrator_mut().handler_mut().handler_mut().on_event(EngineApiRequest::InsertExecutedBlock(executed_block).into());
+        ctx.task_executor().spawn_critical_with_graceful_shutdown_signal(
+            "consensus engine",
+            |shutdown| async move {
+                let mut shutdown = std::pin::pin!(shutdown);
+                if let Some(initial_target) = initial_target {
+                    debug!(target: "reth::cli", %initial_target,  "start backfill sync");
+                    eth_service.orchestrator_mut().start_backfill_sync(initial_target);
+                }
+
+                let mut res = Ok(());
+
+                // advance the chain and await payloads built locally to add into the engine api tree handler to prevent re-execution if that block is received as payload from the CL
+                loop {
+                    tokio::select! {
+                        guard = &mut shutdown => {
+                            debug!(target: "reth::cli", "shutting down consensus engine");
+                            eth_service.orchestrator_mut().shutdown().await;
+                            drop(guard);
+                            break
                         }
-                    }
-                    event =  eth_service.next() => {
-                        let Some(event) = event else { break };
-                        debug!(target: "reth::cli", "Event: {event}");
-                        match event {
-                            ChainEvent::BackfillSyncFinished => {
-                                network_handle.update_sync_state(SyncState::Idle);
-                            }
-                            ChainEvent::BackfillSyncStarted => {
-                                network_handle.update_sync_state(SyncState::Syncing);
-                            }
-                            ChainEvent::FatalError => {
-                                error!(target: "reth::cli", "Fatal error in consensus engine");
-                                res = Err(eyre::eyre!("Fatal error in consensus engine"));
-                                break
+                        payload = built_payloads.select_next_some() => {
+                            if let Some(executed_block) = payload.executed_block() {
+                                debug!(target: "reth::cli", hash=%executed_block.block().hash(),  "inserting built payload");
+                                eth_service.orchestrator_mut().handler_mut().handler_mut().on_event(EngineApiRequest::InsertExecutedBlock(executed_block).into());
                             }
-                            ChainEvent::Handler(ev) => {
-                                if let Some(head) = ev.canonical_header() {
-                                    let head_block = Head {
-                                        number: head.number,
-                                        hash: head.hash(),
-                                        difficulty: head.difficulty,
-                                        timestamp: head.timestamp,
-                                        total_difficulty: chainspec
-                                            .final_paris_total_difficulty(head.number)
-                                            .unwrap_or_default(),
-                                    };
-                                    network_handle.update_status(head_block);
+                        }
+                        event =  eth_service.next() => {
+                            let Some(event) = event else { break };
+                            debug!(target: "reth::cli", "Event: {event}");
+                            match event {
+                                ChainEvent::BackfillSyncFinished => {
+                                    network_handle.update_sync_state(SyncState::Idle);
+                                }
+                                ChainEvent::BackfillSyncStarted => {
+                                    network_handle.update_sync_state(SyncState::Syncing);
+                                }
+                                ChainEvent::BackfillSyncProgress(_) |
+                                ChainEvent::HandlerStalled => {}
+                                ChainEvent::FatalError(err) => {
+                                    error!(target: "reth::cli", %err, "Fatal error in consensus engine");
+                                    res = Err(eyre::eyre!("Fatal error in consensus engine: {err}"));
+                                    break
+                                }
+                                ChainEvent::Handler(ev) => {
+                                    if let Some(head) = ev.canonical_header() {
+                                        let head_block = Head {
+                                            number: head.number,
+                                            hash: head.hash(),
+                                            difficulty: head.difficulty,
+                                            timestamp: head.timestamp,
+                                            total_difficulty: chainspec
+                                                .final_paris_total_difficulty(head.number)
+                                                .unwrap_or_default(),
+                                        };
+                                        network_handle.update_status(head_block);
+                                    }
+                                    event_sender.notify(ev);
                                 }
-                                event_sender.notify(ev);
                             }
                         }
                     }
                 }
-            }
 
-            let _ = exit.send(res);
-        });
+                let _ = exit.send(res);
+            },
+        );
 
         let full_node = FullNode {
             evm_config: ctx.components().evm_config().clone(),
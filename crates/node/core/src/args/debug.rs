@@ -5,7 +5,8 @@ use clap::{
     builder::{PossibleValue, TypedValueParser},
     Arg, Args, Command,
 };
-use std::{collections::HashSet, ffi::OsStr, fmt, path::PathBuf, str::FromStr};
+use humantime::parse_duration;
+use std::{collections::HashSet, ffi::OsStr, fmt, path::PathBuf, str::FromStr, time::Duration};
 use strum::{AsRefStr, EnumIter, IntoStaticStr, ParseError, VariantArray, VariantNames};
 
 /// Parameters for debugging purposes
@@ -87,6 +88,20 @@ pub struct DebugArgs {
         verbatim_doc_comment
     )]
     pub healthy_node_rpc_url: Option<String>,
+
+    /// If provided, the read-only database transactions opened by the blockchain tree to execute
+    /// and validate blocks are instrumented, and a warning is emitted for every transaction held
+    /// open for longer than the given duration.
+    ///
+    /// Parses strings using [`humantime::parse_duration`]
+    /// --debug.long-read-transaction-threshold 30s
+    #[arg(
+        long = "debug.long-read-transaction-threshold",
+        help_heading = "Debug",
+        value_parser = parse_duration,
+        verbatim_doc_comment
+    )]
+    pub long_read_transaction_threshold: Option<Duration>,
 }
 
 impl Default for DebugArgs {
@@ -104,6 +119,7 @@ impl Default for DebugArgs {
             engine_api_store: None,
             invalid_block_hook: Some(InvalidBlockSelection::default()),
             healthy_node_rpc_url: None,
+            long_read_transaction_threshold: None,
         }
     }
 }
@@ -19,7 +19,7 @@ mod op_sepolia;
 use std::fmt::Display;
 
 use alloy_genesis::Genesis;
-use alloy_primitives::{Parity, Signature, B256, U256};
+use alloy_primitives::{Address, Parity, Signature, B256, U256};
 pub use base::BASE_MAINNET;
 pub use base_sepolia::BASE_SEPOLIA;
 pub use dev::OP_DEV;
@@ -28,8 +28,9 @@ pub use op_sepolia::OP_SEPOLIA;
 
 use derive_more::{Constructor, Deref, Into};
 use reth_chainspec::{
-    BaseFeeParams, ChainSpec, DepositContract, EthChainSpec, EthereumHardforks, ForkFilter, ForkId,
-    Hardforks, Head,
+    once_cell_set, BaseFeeParams, BaseFeeParamsKind, Chain, ChainSpec, ChainSpecBuilder,
+    DepositContract, EthChainSpec, EthereumHardfork, EthereumHardforks, ForkCondition, ForkFilter,
+    ForkId, Hardfork, Hardforks, Head,
 };
 use reth_network_peers::NodeRecord;
 use reth_primitives_traits::Header;
@@ -127,6 +128,247 @@ impl EthereumHardforks for OpChainSpec {
     }
 }
 
+/// A helper to build custom OP stack chain specs
+///
+/// Wraps [`ChainSpecBuilder`], which builds the fork schedule, and adds the parts of the spec that
+/// are specific to OP stack chains.
+#[derive(Debug, Default, Clone)]
+pub struct OpChainSpecBuilder {
+    /// The builder of the fork schedule.
+    inner: ChainSpecBuilder,
+    chain: Option<Chain>,
+    genesis: Option<Genesis>,
+    genesis_hash: Option<B256>,
+    base_fee_params: Option<BaseFeeParamsKind>,
+    deposit_contract: Option<DepositContract>,
+    prune_delete_limit: Option<usize>,
+    max_gas_limit: Option<u64>,
+}
+
+impl OpChainSpecBuilder {
+    /// Construct a new builder from the OP mainnet chain spec.
+    pub fn mainnet() -> Self {
+        Self::from_spec(&OP_MAINNET)
+    }
+
+    /// Construct a new builder from the OP sepolia chain spec.
+    pub fn sepolia() -> Self {
+        Self::from_spec(&OP_SEPOLIA)
+    }
+
+    /// Construct a new builder from the OP mainnet chain spec, replacing its chain ID and genesis
+    /// block while keeping its fork schedule.
+    pub fn clone_with_chain(chain: Chain, genesis: Genesis) -> Self {
+        Self::mainnet().chain(chain).genesis(genesis)
+    }
+
+    /// Construct a new builder from the given chain spec, keeping all of its parameters.
+    fn from_spec(spec: &OpChainSpec) -> Self {
+        Self {
+            inner: ChainSpecBuilder::default().with_forks(spec.hardforks.clone()),
+            chain: Some(spec.chain),
+            genesis: Some(spec.genesis.clone()),
+            genesis_hash: spec.genesis_hash.get().copied(),
+            base_fee_params: Some(spec.base_fee_params.clone()),
+            deposit_contract: spec.deposit_contract,
+            prune_delete_limit: Some(spec.prune_delete_limit),
+            max_gas_limit: Some(spec.max_gas_limit),
+        }
+    }
+}
+
+impl OpChainSpecBuilder {
+    /// Set the chain ID
+    pub const fn chain(mut self, chain: Chain) -> Self {
+        self.chain = Some(chain);
+        self
+    }
+
+    /// Set the genesis block, resetting the hash of the genesis block.
+    pub fn genesis(mut self, genesis: Genesis) -> Self {
+        self.genesis = Some(genesis);
+        self.genesis_hash = None;
+        self
+    }
+
+    /// Set the timestamp of the genesis block, keeping the rest of the genesis and resetting its
+    /// hash. If no genesis is set, a default genesis with the given timestamp is used.
+    ///
+    /// Timestamp based forks are not shifted: forks enabled at genesis, e.g. with
+    /// [`Self::ecotone_activated`], are active from the genesis block on, and forks scheduled
+    /// after genesis should be set with [`Self::with_fork`] relative to the new timestamp.
+    pub fn genesis_timestamp(mut self, timestamp: u64) -> Self {
+        self.genesis.get_or_insert_with(Genesis::default).timestamp = timestamp;
+        self.genesis_hash = None;
+        self
+    }
+
+    /// Set the hash of the genesis block.
+    ///
+    /// If not set, the hash is computed from the genesis header in [`Self::build`].
+    pub const fn genesis_hash(mut self, genesis_hash: B256) -> Self {
+        self.genesis_hash = Some(genesis_hash);
+        self
+    }
+
+    /// Set the parameters that configure how a block's base fee is computed.
+    pub fn base_fee_params(mut self, base_fee_params: BaseFeeParamsKind) -> Self {
+        self.base_fee_params = Some(base_fee_params);
+        self
+    }
+
+    /// Set the deposit contract deployed at the given block, emitting deposit events with the
+    /// given topic.
+    pub const fn deposit_contract(
+        mut self,
+        address: Address,
+        deployed_block: u64,
+        topic: B256,
+    ) -> Self {
+        self.deposit_contract = Some(DepositContract::new(address, deployed_block, topic));
+        self
+    }
+
+    /// Add the given fork with the given activation condition to the spec.
+    pub fn with_fork<H: Hardfork>(mut self, fork: H, condition: ForkCondition) -> Self {
+        self.inner = self.inner.with_fork(fork, condition);
+        self
+    }
+
+    /// Remove the given fork from the spec.
+    pub fn without_fork<H: Hardfork>(mut self, fork: H) -> Self {
+        self.inner = self.inner.without_fork(fork);
+        self
+    }
+
+    /// Remove the given forks from the spec, e.g. [`EthereumHardfork`]s or
+    /// [`OptimismHardfork`](reth_optimism_forks::OptimismHardfork)s.
+    pub fn without_forks<H: Hardfork>(mut self, forks: impl IntoIterator<Item = H>) -> Self {
+        for fork in forks {
+            self.inner = self.inner.without_fork(fork);
+        }
+        self
+    }
+
+    /// Enable Bedrock at genesis, along with all Ethereum hardforks up to Paris.
+    pub fn bedrock_activated(mut self) -> Self {
+        self.inner = self.inner.bedrock_activated();
+        self
+    }
+
+    /// Enable Regolith at genesis
+    pub fn regolith_activated(mut self) -> Self {
+        self.inner = self.inner.regolith_activated();
+        self
+    }
+
+    /// Enable Canyon at genesis
+    pub fn canyon_activated(mut self) -> Self {
+        self.inner = self.inner.canyon_activated();
+        self
+    }
+
+    /// Enable Ecotone at genesis
+    pub fn ecotone_activated(mut self) -> Self {
+        self.inner = self.inner.ecotone_activated();
+        self
+    }
+
+    /// Enable Fjord at genesis
+    pub fn fjord_activated(mut self) -> Self {
+        self.inner = self.inner.fjord_activated();
+        self
+    }
+
+    /// Enable Granite at genesis
+    pub fn granite_activated(mut self) -> Self {
+        self.inner = self.inner.granite_activated();
+        self
+    }
+
+    /// Returns the names of the forks that are active at the given block number and timestamp, in
+    /// the order they were added to the builder.
+    ///
+    /// TTD based forks are only considered active if their activation block is known and is not
+    /// higher than the given block.
+    pub fn active_forks_at(&self, block: u64, timestamp: u64) -> Vec<&'static str> {
+        self.inner
+            .hardforks()
+            .forks_iter()
+            .filter(|(_, condition)| {
+                condition.active_at_block(block) || condition.active_at_timestamp(timestamp)
+            })
+            .map(|(fork, _)| fork.name())
+            .collect()
+    }
+
+    /// Build the resulting [`OpChainSpec`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the spec is invalid, see [`Self::try_build`].
+    pub fn build(self) -> OpChainSpec {
+        self.try_build().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Build the resulting [`OpChainSpec`], returning an error if the chain ID or genesis is not
+    /// set ([`Self::chain`] and [`Self::genesis`]), or if the Paris activation block is unknown.
+    ///
+    /// Paris can be activated either by the terminal total difficulty with a known fork block, or
+    /// by a block number. In the latter case the final total difficulty is zero, as OP stack chains
+    /// never had proof-of-work blocks.
+    pub fn try_build(self) -> Result<OpChainSpec, OpChainSpecBuilderError> {
+        let chain = self.chain.ok_or(OpChainSpecBuilderError::MissingChain)?;
+        let genesis = self.genesis.ok_or(OpChainSpecBuilderError::MissingGenesis)?;
+        let paris = self.inner.hardforks().get(EthereumHardfork::Paris);
+        if let Some(ForkCondition::TTD { fork_block: None, total_difficulty }) = paris {
+            return Err(OpChainSpecBuilderError::UnknownParisBlock { total_difficulty })
+        }
+
+        let mut inner = self.inner.chain(chain).genesis(genesis).build();
+        if let Some(ForkCondition::Block(fork_block)) = paris {
+            inner.paris_block_and_final_difficulty = Some((fork_block, U256::ZERO));
+        }
+        if let Some(genesis_hash) = self.genesis_hash {
+            inner.genesis_hash = once_cell_set(genesis_hash);
+        }
+        if let Some(base_fee_params) = self.base_fee_params {
+            inner.base_fee_params = base_fee_params;
+        }
+        inner.deposit_contract = self.deposit_contract;
+        if let Some(prune_delete_limit) = self.prune_delete_limit {
+            inner.prune_delete_limit = prune_delete_limit;
+        }
+        if let Some(max_gas_limit) = self.max_gas_limit {
+            inner.max_gas_limit = max_gas_limit;
+        }
+        let spec = OpChainSpec { inner };
+
+        // Compute the genesis hash once if it wasn't provided, so that it's cached for all
+        // consumers of the spec.
+        spec.genesis_hash();
+
+        Ok(spec)
+    }
+}
+
+/// Errors returned by [`OpChainSpecBuilder::try_build`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OpChainSpecBuilderError {
+    /// The chain ID is not set.
+    #[error("The chain is required")]
+    MissingChain,
+    /// The genesis block is not set.
+    #[error("The genesis is required")]
+    MissingGenesis,
+    /// Paris is activated by the terminal total difficulty, but its activation block is not set.
+    #[error("Paris is activated at total difficulty {total_difficulty} without a fork block")]
+    UnknownParisBlock {
+        /// The terminal total difficulty of the Paris fork.
+        total_difficulty: U256,
+    },
+}
+
 #[cfg(test)]
 mod tests {
     use alloy_genesis::Genesis;
@@ -341,6 +583,195 @@ mod tests {
         assert!(!OP_MAINNET.is_bedrock_active_at_block(1))
     }
 
+    #[test]
+    fn builder_deposit_contract() {
+        let address = Address::with_last_byte(0x42);
+        let topic = B256::with_last_byte(0x69);
+
+        assert_eq!(OpChainSpecBuilder::mainnet().build().deposit_contract, None);
+
+        let spec = OpChainSpecBuilder::mainnet().deposit_contract(address, 100, topic).build();
+        assert_eq!(spec.deposit_contract, Some(DepositContract::new(address, 100, topic)));
+        assert_eq!(spec.deposit_contract(), Some(&DepositContract::new(address, 100, topic)));
+    }
+
+    #[test]
+    fn builder_genesis_hash() {
+        // The known hash of the preset is kept
+        let spec = OpChainSpecBuilder::mainnet().build();
+        assert_eq!(spec.genesis_hash(), OP_MAINNET.genesis_hash());
+
+        // The hash is computed and cached in `build` if the genesis is replaced
+        let spec = OpChainSpecBuilder::mainnet().genesis(Genesis::default()).build();
+        assert_eq!(spec.inner.genesis_hash.get(), Some(&spec.genesis_header().hash_slow()));
+
+        // The provided hash is used as is
+        let genesis_hash = B256::with_last_byte(0x42);
+        let spec = OpChainSpecBuilder::mainnet().genesis_hash(genesis_hash).build();
+        assert_eq!(spec.genesis_hash(), genesis_hash);
+    }
+
+    #[test]
+    fn builder_clone_with_chain() {
+        let genesis = Genesis { timestamp: 42, ..Default::default() };
+        let spec =
+            OpChainSpecBuilder::clone_with_chain(Chain::from_id(1337), genesis.clone()).build();
+
+        assert_eq!(spec.chain, Chain::from_id(1337));
+        assert_eq!(spec.genesis, genesis);
+        assert_ne!(spec.genesis_hash(), OP_MAINNET.genesis_hash());
+        assert_eq!(
+            spec.hardforks
+                .forks_iter()
+                .map(|(fork, condition)| (fork.name(), condition))
+                .collect::<Vec<_>>(),
+            OP_MAINNET
+                .hardforks
+                .forks_iter()
+                .map(|(fork, condition)| (fork.name(), condition))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn builder_genesis_timestamp() {
+        let genesis_timestamp = 1_700_000_000;
+        let spec = OpChainSpecBuilder::default()
+            .chain(Chain::from_id(1337))
+            .genesis_timestamp(genesis_timestamp)
+            .ecotone_activated()
+            .with_fork(OptimismHardfork::Fjord, ForkCondition::Timestamp(genesis_timestamp + 100))
+            .build();
+
+        assert_eq!(spec.genesis.timestamp, genesis_timestamp);
+        assert_eq!(spec.genesis_header().timestamp, genesis_timestamp);
+
+        // Forks enabled at genesis are active from the genesis block on, later forks are
+        // activated relative to the genesis timestamp
+        assert!(spec.is_ecotone_active_at_timestamp(genesis_timestamp));
+        assert!(!spec.is_fork_active_at_timestamp(OptimismHardfork::Fjord, genesis_timestamp));
+        assert!(!spec.is_fork_active_at_timestamp(OptimismHardfork::Fjord, genesis_timestamp + 99));
+        assert!(spec.is_fork_active_at_timestamp(OptimismHardfork::Fjord, genesis_timestamp + 100));
+
+        // The rest of the genesis is kept
+        let spec = OpChainSpecBuilder::mainnet().genesis_timestamp(42).build();
+        assert_eq!(spec.genesis, Genesis { timestamp: 42, ..OP_MAINNET.genesis.clone() });
+        assert_ne!(spec.genesis_hash(), OP_MAINNET.genesis_hash());
+    }
+
+    #[test]
+    fn builder_without_forks() {
+        let spec = OpChainSpecBuilder::mainnet()
+            .with_fork(EthereumHardfork::Prague, ForkCondition::Timestamp(0))
+            .without_forks([EthereumHardfork::Cancun, EthereumHardfork::Prague])
+            .build();
+        assert_eq!(spec.fork(EthereumHardfork::Cancun), ForkCondition::Never);
+        assert_eq!(spec.fork(EthereumHardfork::Prague), ForkCondition::Never);
+        assert!(!spec.is_cancun_active_at_timestamp(u64::MAX));
+        assert!(!spec.is_prague_active_at_timestamp(u64::MAX));
+        assert!(spec.is_shanghai_active_at_timestamp(u64::MAX));
+
+        let spec = OpChainSpecBuilder::mainnet()
+            .without_forks([OptimismHardfork::Fjord, OptimismHardfork::Granite])
+            .build();
+        assert_eq!(spec.fork(OptimismHardfork::Fjord), ForkCondition::Never);
+        assert_eq!(spec.fork(OptimismHardfork::Granite), ForkCondition::Never);
+        assert!(spec.is_ecotone_active_at_timestamp(u64::MAX));
+    }
+
+    #[test]
+    fn builder_presets() {
+        for (builder, source) in [
+            (OpChainSpecBuilder::mainnet(), &OP_MAINNET),
+            (OpChainSpecBuilder::sepolia(), &OP_SEPOLIA),
+        ] {
+            let spec = builder.build();
+            assert_eq!(spec.inner.genesis_hash.get(), source.inner.genesis_hash.get());
+            assert_eq!(spec.prune_delete_limit, source.prune_delete_limit);
+            assert_eq!(spec.prune_delete_limit, 10000);
+            assert_eq!(spec.max_gas_limit, source.max_gas_limit);
+        }
+    }
+
+    #[test]
+    fn builder_sepolia() {
+        let spec = OpChainSpecBuilder::sepolia().build();
+
+        assert_eq!(spec.chain, Chain::from_id(11155420));
+        assert_eq!(spec.genesis_hash(), OP_SEPOLIA.genesis_hash());
+        assert_eq!(spec.fork(OptimismHardfork::Canyon), ForkCondition::Timestamp(1699981200));
+        assert_eq!(spec.fork(OptimismHardfork::Ecotone), ForkCondition::Timestamp(1708534800));
+        assert_eq!(spec.base_fee_params, OP_SEPOLIA.base_fee_params);
+    }
+
+    #[test]
+    fn builder_active_forks_at() {
+        let builder = OpChainSpecBuilder::default()
+            .with_fork(EthereumHardfork::London, ForkCondition::Block(5))
+            .with_fork(
+                EthereumHardfork::Paris,
+                ForkCondition::TTD { fork_block: None, total_difficulty: U256::ZERO },
+            )
+            .with_fork(OptimismHardfork::Bedrock, ForkCondition::Block(10))
+            .with_fork(OptimismHardfork::Regolith, ForkCondition::Timestamp(100))
+            .with_fork(OptimismHardfork::Canyon, ForkCondition::Timestamp(200));
+
+        assert!(builder.active_forks_at(0, 0).is_empty());
+        assert_eq!(builder.active_forks_at(5, 0), ["London"]);
+        assert_eq!(builder.active_forks_at(10, 150), ["London", "Bedrock", "Regolith"]);
+
+        // TTD fork is active once its block is known
+        let builder = builder.with_fork(
+            EthereumHardfork::Paris,
+            ForkCondition::TTD { fork_block: Some(10), total_difficulty: U256::ZERO },
+        );
+        assert_eq!(builder.active_forks_at(9, 0), ["London"]);
+        assert_eq!(
+            builder.active_forks_at(10, 200),
+            ["London", "Paris", "Bedrock", "Regolith", "Canyon"]
+        );
+    }
+
+    #[test]
+    fn builder_paris_block() {
+        // Paris activated by the terminal total difficulty at a known block
+        let spec = OpChainSpecBuilder::mainnet()
+            .with_fork(
+                EthereumHardfork::Paris,
+                ForkCondition::TTD { fork_block: Some(10), total_difficulty: U256::from(5) },
+            )
+            .build();
+        assert_eq!(spec.paris_block_and_final_difficulty, Some((10, U256::from(5))));
+
+        // Paris activated by the block number
+        let spec = OpChainSpecBuilder::mainnet()
+            .with_fork(EthereumHardfork::Paris, ForkCondition::Block(10))
+            .build();
+        assert_eq!(spec.paris_block_and_final_difficulty, Some((10, U256::ZERO)));
+        assert_eq!(spec.final_paris_total_difficulty(11), Some(U256::ZERO));
+
+        // No Paris
+        let spec = OpChainSpecBuilder::mainnet().without_fork(EthereumHardfork::Paris).build();
+        assert_eq!(spec.paris_block_and_final_difficulty, None);
+    }
+
+    #[test]
+    fn builder_inconsistent_paris() {
+        let builder = OpChainSpecBuilder::mainnet().with_fork(
+            EthereumHardfork::Paris,
+            ForkCondition::TTD { fork_block: None, total_difficulty: U256::from(5) },
+        );
+        assert_eq!(
+            builder.try_build(),
+            Err(OpChainSpecBuilderError::UnknownParisBlock { total_difficulty: U256::from(5) })
+        );
+
+        assert_eq!(
+            OpChainSpecBuilder::default().try_build(),
+            Err(OpChainSpecBuilderError::MissingChain)
+        );
+    }
+
     #[test]
     fn parse_optimism_hardforks() {
         let geth_genesis = r#"
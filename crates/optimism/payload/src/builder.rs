@@ -207,10 +207,10 @@ where
     pre_block_beacon_root_contract_call(
         &mut db,
         &evm_config,
-        &chain_spec,
         &initialized_cfg,
         &initialized_block_env,
         attributes.payload_attributes.parent_beacon_block_root,
+        None,
     )
     .map_err(|err| {
         warn!(target: "payload_builder",
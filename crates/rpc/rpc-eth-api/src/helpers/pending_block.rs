@@ -268,10 +268,10 @@ pub trait LoadPendingBlock: EthApiTypes {
             pre_block_beacon_root_contract_call(
                 &mut db,
                 self.evm_config(),
-                chain_spec.as_ref(),
                 &cfg,
                 &block_env,
                 origin.header().parent_beacon_block_root,
+                None,
             )
             .map_err(|err| EthApiError::Internal(err.into()))?;
             origin.header().parent_beacon_block_root
@@ -0,0 +1,273 @@
+//! Instrumentation of read-only database transactions.
+
+use metrics::{Counter, Histogram, Label};
+use reth_db_api::{
+    database::Database,
+    database_metrics::{DatabaseMetadata, DatabaseMetadataValue, DatabaseMetrics},
+    table::{DupSort, Table},
+    transaction::DbTx,
+    DatabaseError,
+};
+use reth_metrics::Metrics;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Default duration after which a read-only transaction is considered long-lived.
+pub const DEFAULT_LONG_LIVED_PROVIDER_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// A [`Database`] wrapper that instruments the read-only transactions it opens.
+///
+/// Long-lived read transactions pin the MVCC snapshot of the database, which prevents pages from
+/// being reused and grows the freelist. The lifetime of every read-only transaction opened by this
+/// database is recorded, and a warning is emitted once a transaction is closed after living longer
+/// than the configured threshold.
+///
+/// The lifetime is tracked by the transaction itself, so a [`ProviderFactory`] over this database
+/// keeps tracking it after the transaction is taken out of the read-only provider, e.g. with
+/// [`DBProvider::into_tx`](crate::DBProvider::into_tx) or by turning the provider into a state
+/// provider. See [`ProviderFactory::with_instrumented_read_transactions`].
+///
+/// [`ProviderFactory`]: crate::ProviderFactory
+/// [`ProviderFactory::with_instrumented_read_transactions`]: crate::ProviderFactory::with_instrumented_read_transactions
+#[derive(Debug, Clone)]
+pub struct InstrumentedDatabase<DB> {
+    /// Inner database.
+    db: DB,
+    /// Lifetime after which a read-only transaction is considered long-lived.
+    long_lived_threshold: Duration,
+    /// Read-only transaction metrics.
+    metrics: ReadOnlyProviderMetrics,
+}
+
+impl<DB> InstrumentedDatabase<DB> {
+    /// Wraps the given database, using [`DEFAULT_LONG_LIVED_PROVIDER_THRESHOLD`].
+    pub fn new(db: DB) -> Self {
+        Self {
+            db,
+            long_lived_threshold: DEFAULT_LONG_LIVED_PROVIDER_THRESHOLD,
+            metrics: ReadOnlyProviderMetrics::default(),
+        }
+    }
+
+    /// Sets the lifetime after which a read-only transaction is considered long-lived.
+    pub const fn with_long_lived_threshold(mut self, threshold: Duration) -> Self {
+        self.long_lived_threshold = threshold;
+        self
+    }
+
+    /// Returns the inner database.
+    pub const fn inner(&self) -> &DB {
+        &self.db
+    }
+}
+
+impl<DB: Database> Database for InstrumentedDatabase<DB> {
+    type TX = InstrumentedTx<DB::TX>;
+    type TXMut = DB::TXMut;
+
+    #[track_caller]
+    fn tx(&self) -> Result<Self::TX, DatabaseError> {
+        Ok(InstrumentedTx {
+            tx: self.db.tx()?,
+            guard: LifetimeGuard {
+                opened_at: Instant::now(),
+                long_lived_threshold: self.long_lived_threshold,
+                metrics: self.metrics.clone(),
+            },
+        })
+    }
+
+    #[track_caller]
+    fn tx_mut(&self) -> Result<Self::TXMut, DatabaseError> {
+        self.db.tx_mut()
+    }
+}
+
+impl<DB: DatabaseMetrics> DatabaseMetrics for InstrumentedDatabase<DB> {
+    fn report_metrics(&self) {
+        self.db.report_metrics()
+    }
+
+    fn gauge_metrics(&self) -> Vec<(&'static str, f64, Vec<Label>)> {
+        self.db.gauge_metrics()
+    }
+
+    fn counter_metrics(&self) -> Vec<(&'static str, u64, Vec<Label>)> {
+        self.db.counter_metrics()
+    }
+
+    fn histogram_metrics(&self) -> Vec<(&'static str, f64, Vec<Label>)> {
+        self.db.histogram_metrics()
+    }
+}
+
+impl<DB: DatabaseMetadata> DatabaseMetadata for InstrumentedDatabase<DB> {
+    fn metadata(&self) -> DatabaseMetadataValue {
+        self.db.metadata()
+    }
+}
+
+/// A read-only transaction opened by [`InstrumentedDatabase`].
+///
+/// Its lifetime is recorded when it's committed, aborted or dropped.
+#[derive(Debug)]
+pub struct InstrumentedTx<TX> {
+    /// Inner transaction.
+    tx: TX,
+    /// Records the lifetime of the transaction on drop.
+    guard: LifetimeGuard,
+}
+
+impl<TX: DbTx> DbTx for InstrumentedTx<TX> {
+    type Cursor<T: Table> = TX::Cursor<T>;
+    type DupCursor<T: DupSort> = TX::DupCursor<T>;
+
+    fn get<T: Table>(&self, key: T::Key) -> Result<Option<T::Value>, DatabaseError> {
+        self.tx.get::<T>(key)
+    }
+
+    fn commit(self) -> Result<bool, DatabaseError> {
+        let Self { tx, guard } = self;
+        let result = tx.commit();
+        drop(guard);
+        result
+    }
+
+    fn abort(self) {
+        let Self { tx, guard } = self;
+        tx.abort();
+        drop(guard);
+    }
+
+    fn cursor_read<T: Table>(&self) -> Result<Self::Cursor<T>, DatabaseError> {
+        self.tx.cursor_read()
+    }
+
+    fn cursor_dup_read<T: DupSort>(&self) -> Result<Self::DupCursor<T>, DatabaseError> {
+        self.tx.cursor_dup_read()
+    }
+
+    fn entries<T: Table>(&self) -> Result<usize, DatabaseError> {
+        self.tx.entries::<T>()
+    }
+
+    fn disable_long_read_transaction_safety(&mut self) {
+        self.tx.disable_long_read_transaction_safety()
+    }
+}
+
+/// Records the lifetime of a read-only transaction on drop.
+#[derive(Debug)]
+struct LifetimeGuard {
+    opened_at: Instant,
+    long_lived_threshold: Duration,
+    metrics: ReadOnlyProviderMetrics,
+}
+
+impl Drop for LifetimeGuard {
+    fn drop(&mut self) {
+        let lifetime = self.opened_at.elapsed();
+        self.metrics.lifetime.record(lifetime);
+
+        if lifetime >= self.long_lived_threshold {
+            self.metrics.long_lived.increment(1);
+            warn!(
+                target: "providers::db",
+                ?lifetime,
+                threshold = ?self.long_lived_threshold,
+                "Read-only database transaction was held open for too long"
+            );
+        }
+    }
+}
+
+/// Read-only database transaction metrics
+#[derive(Metrics, Clone)]
+#[metrics(scope = "storage.providers.database.ro")]
+struct ReadOnlyProviderMetrics {
+    /// Lifetime of read-only transactions
+    lifetime: Histogram,
+    /// Number of read-only transactions that outlived the long-lived threshold
+    long_lived: Counter,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::create_test_provider_factory, DatabaseProviderFactory,
+        TryIntoHistoricalStateProvider,
+    };
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    /// Opens a read-only provider with a long-lived threshold of 50ms, passes it to the given
+    /// closure and returns the number of long-lived transactions recorded.
+    fn long_lived_count(
+        hold: impl FnOnce(<InstrumentedFactory as DatabaseProviderFactory>::Provider),
+    ) -> u64 {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let factory = create_test_provider_factory()
+                .with_instrumented_read_transactions(Duration::from_millis(50));
+            hold(factory.database_provider_ro().unwrap());
+        });
+
+        snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .find_map(|(key, _, _, value)| match value {
+                DebugValue::Counter(count)
+                    if key.key().name() == "storage.providers.database.ro.long_lived" =>
+                {
+                    Some(count)
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    type InstrumentedFactory = crate::ProviderFactory<
+        reth_node_types::NodeTypesWithDBAdapter<
+            crate::test_utils::MockNodeTypesWithDB,
+            InstrumentedDatabase<
+                std::sync::Arc<reth_db::test_utils::TempDatabase<reth_db::DatabaseEnv>>,
+            >,
+        >,
+    >;
+
+    #[test]
+    fn long_lived_provider() {
+        assert_eq!(long_lived_count(drop), 0);
+        assert_eq!(
+            long_lived_count(|provider| {
+                std::thread::sleep(Duration::from_millis(100));
+                drop(provider);
+            }),
+            1
+        );
+    }
+
+    #[test]
+    fn long_lived_transaction_taken_out_of_provider() {
+        // The lifetime is tracked until the transaction itself is closed
+        assert_eq!(
+            long_lived_count(|provider| {
+                let tx = provider.into_tx();
+                std::thread::sleep(Duration::from_millis(100));
+                tx.commit().unwrap();
+            }),
+            1
+        );
+        assert_eq!(
+            long_lived_count(|provider| {
+                let state_provider = provider.try_into_history_at_block(0).unwrap();
+                std::thread::sleep(Duration::from_millis(100));
+                drop(state_provider);
+            }),
+            1
+        );
+    }
+}
@@ -3,9 +3,10 @@ use crate::{
     to_range,
     traits::{BlockSource, ReceiptProvider},
     BlockHashReader, BlockNumReader, BlockReader, ChainSpecProvider, DatabaseProviderFactory,
-    EvmEnvProvider, HeaderProvider, HeaderSyncGap, HeaderSyncGapProvider, ProviderError,
-    PruneCheckpointReader, RequestsProvider, StageCheckpointReader, StateProviderBox,
-    StaticFileProviderFactory, TransactionVariant, TransactionsProvider, WithdrawalsProvider,
+    DatabaseStateProviderFactory, EvmEnvProvider, HeaderProvider, HeaderSyncGap,
+    HeaderSyncGapProvider, ProviderError, PruneCheckpointReader, RequestsProvider,
+    StageCheckpointReader, StateProviderBox, StaticFileProviderFactory, TransactionVariant,
+    TransactionsProvider, WithdrawalsProvider,
 };
 use alloy_eips::BlockHashOrNumber;
 use alloy_primitives::{Address, BlockHash, BlockNumber, TxHash, TxNumber, B256, U256};
@@ -15,7 +16,7 @@ use reth_db::{init_db, mdbx::DatabaseArguments, DatabaseEnv};
 use reth_db_api::{database::Database, models::StoredBlockBodyIndices};
 use reth_errors::{RethError, RethResult};
 use reth_evm::ConfigureEvmEnv;
-use reth_node_types::NodeTypesWithDB;
+use reth_node_types::{NodeTypesWithDB, NodeTypesWithDBAdapter};
 use reth_primitives::{
     Block, BlockWithSenders, Header, Receipt, SealedBlock, SealedBlockWithSenders, SealedHeader,
     StaticFileSegment, TransactionMeta, TransactionSigned, TransactionSignedNoHash, Withdrawal,
@@ -30,6 +31,7 @@ use std::{
     ops::{RangeBounds, RangeInclusive},
     path::Path,
     sync::Arc,
+    time::Duration,
 };
 use tokio::sync::watch;
 use tracing::trace;
@@ -41,6 +43,11 @@ use super::ProviderNodeTypes;
 
 mod metrics;
 
+mod instrumented;
+pub use instrumented::{
+    InstrumentedDatabase, InstrumentedTx, DEFAULT_LONG_LIVED_PROVIDER_THRESHOLD,
+};
+
 /// A common provider that fetches data from a database or static file.
 ///
 /// This provider implements most provider or provider factory traits.
@@ -97,6 +104,24 @@ impl<N: NodeTypesWithDB> ProviderFactory<N> {
         &self.db
     }
 
+    /// Returns a provider factory over the same database and static files, with the read-only
+    /// database transactions instrumented by [`InstrumentedDatabase`].
+    ///
+    /// A warning is emitted for every read-only transaction that is held open for longer than the
+    /// given threshold.
+    pub fn with_instrumented_read_transactions(
+        self,
+        long_lived_threshold: Duration,
+    ) -> ProviderFactory<NodeTypesWithDBAdapter<N, InstrumentedDatabase<N::DB>>> {
+        let Self { db, chain_spec, static_file_provider, prune_modes } = self;
+        ProviderFactory {
+            db: InstrumentedDatabase::new(db).with_long_lived_threshold(long_lived_threshold),
+            chain_spec,
+            static_file_provider,
+            prune_modes,
+        }
+    }
+
     #[cfg(any(test, feature = "test-utils"))]
     /// Consumes Self and returns DB
     pub fn into_db(self) -> N::DB {
@@ -198,6 +223,18 @@ impl<N: ProviderNodeTypes> DatabaseProviderFactory for ProviderFactory<N> {
     }
 }
 
+impl<N: ProviderNodeTypes> DatabaseStateProviderFactory for ProviderFactory<N> {
+    type StateProvider = StateProviderBox;
+
+    fn latest_state(&self) -> ProviderResult<Self::StateProvider> {
+        self.latest()
+    }
+
+    fn history_state_at(&self, block: BlockNumber) -> ProviderResult<Self::StateProvider> {
+        self.history_by_block_number(block)
+    }
+}
+
 impl<N: NodeTypesWithDB> StaticFileProviderFactory for ProviderFactory<N> {
     /// Returns static file provider
     fn static_file_provider(&self) -> StaticFileProvider {
@@ -631,8 +668,12 @@ mod tests {
     use super::*;
     use crate::{
         providers::{StaticFileProvider, StaticFileWriter},
-        test_utils::{blocks::TEST_BLOCK, create_test_provider_factory, MockNodeTypesWithDB},
-        BlockHashReader, BlockNumReader, BlockWriter, HeaderSyncGapProvider, TransactionsProvider,
+        test_utils::{
+            blocks::{BlockchainTestData, TEST_BLOCK},
+            create_test_provider_factory, MockNodeTypesWithDB,
+        },
+        AccountReader, BlockHashReader, BlockNumReader, BlockWriter, HeaderProvider,
+        HeaderSyncGapProvider, StateProvider, TransactionsProvider,
     };
     use alloy_primitives::{TxNumber, B256, U256};
     use assert_matches::assert_matches;
@@ -643,6 +684,7 @@ mod tests {
         tables,
         test_utils::{create_test_static_files_dir, ERROR_TEMPDIR},
     };
+    use reth_db_api::transaction::DbTxMut;
     use reth_primitives::StaticFileSegment;
     use reth_prune_types::{PruneMode, PruneModes};
     use reth_storage_errors::provider::ProviderError;
@@ -810,4 +852,144 @@ mod tests {
         assert_eq!(gap.local_head, head);
         assert_eq!(gap.target.tip(), consensus_tip.into());
     }
+
+    #[test]
+    fn sealed_headers_range() {
+        let factory = create_test_provider_factory();
+        let mut rng = generators::rng();
+
+        let headers =
+            (0..5).map(|number| random_header(&mut rng, number, None)).collect::<Vec<_>>();
+        let provider = factory.provider_rw().unwrap();
+        for header in &headers {
+            provider
+                .tx_ref()
+                .put::<tables::Headers>(header.number, header.header().clone())
+                .unwrap();
+            provider
+                .tx_ref()
+                .put::<tables::CanonicalHeaders>(header.number, header.hash())
+                .unwrap();
+        }
+        provider.commit().unwrap();
+
+        let provider = factory.provider().unwrap();
+        let sealed_headers = provider.sealed_headers_range(1..=3).unwrap();
+        assert_eq!(sealed_headers, headers[1..=3]);
+        for header in sealed_headers {
+            assert_eq!(header.hash(), header.header().hash_slow());
+        }
+
+        // Missing canonical hash is reported
+        let provider = factory.provider_rw().unwrap();
+        provider.tx_ref().delete::<tables::CanonicalHeaders>(4, None).unwrap();
+        provider.commit().unwrap();
+        assert_matches!(
+            factory.provider().unwrap().sealed_headers_range(3..=4),
+            Err(ProviderError::HeaderNotFound(block)) if block.as_number() == Some(4)
+        );
+    }
+
+    #[test]
+    fn database_state_provider_factory() {
+        let factory = create_test_provider_factory();
+        let data = BlockchainTestData::default();
+        let account = Address::new([0x60; 20]);
+
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw.insert_block(data.genesis.clone().try_seal_with_senders().unwrap()).unwrap();
+        let (blocks, execution_outcomes): (Vec<_>, Vec<_>) =
+            data.blocks.into_iter().take(2).unzip();
+        let mut execution_outcome = execution_outcomes[0].clone();
+        execution_outcome.extend(execution_outcomes[1].clone());
+        provider_rw
+            .append_blocks_with_state(
+                blocks,
+                execution_outcome,
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap();
+        provider_rw.commit().unwrap();
+
+        assert_eq!(
+            factory.history_state_at(1).unwrap().account_balance(account).unwrap(),
+            Some(U256::from(10))
+        );
+        assert_eq!(
+            factory.history_state_at(2).unwrap().account_balance(account).unwrap(),
+            Some(U256::from(20))
+        );
+        assert_eq!(
+            factory.latest_state().unwrap().account_balance(account).unwrap(),
+            Some(U256::from(20))
+        );
+    }
+
+    #[test]
+    fn database_provider_ro_at() {
+        let factory = create_test_provider_factory();
+        let data = BlockchainTestData::default();
+        let account = Address::new([0x60; 20]);
+        let (block1, outcome1) = data.blocks[0].clone();
+        let (block2, outcome2) = data.blocks[1].clone();
+
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw.insert_block(data.genesis.try_seal_with_senders().unwrap()).unwrap();
+        provider_rw
+            .append_blocks_with_state(
+                vec![block1],
+                outcome1,
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap();
+        provider_rw.commit().unwrap();
+
+        assert_matches!(
+            factory.database_provider_ro_at(2),
+            Err(ProviderError::HeaderNotFound(block)) if block == 2.into()
+        );
+
+        let first = factory.database_provider_ro_at(1).unwrap();
+        let second = factory.database_provider_ro_at(1).unwrap();
+        let header = first.header_by_number(1).unwrap();
+        assert!(header.is_some());
+        assert_eq!(
+            first.basic_account(account).unwrap().map(|account| account.balance),
+            Some(U256::from(10))
+        );
+
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw
+            .append_blocks_with_state(
+                vec![block2],
+                outcome2,
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap();
+        provider_rw.commit().unwrap();
+
+        // Providers opened at block 1 keep observing the state after block 1.
+        assert_eq!(second.header_by_number(1).unwrap(), header);
+        assert_eq!(first.header_by_number(1).unwrap(), header);
+        assert_eq!(first.last_block_number().unwrap(), 1);
+        assert_eq!(second.header_by_number(2).unwrap(), None);
+        assert_eq!(
+            second.basic_account(account).unwrap().map(|account| account.balance),
+            Some(U256::from(10))
+        );
+
+        // The database advanced past block 1, so a snapshot at it can no longer be opened.
+        assert_matches!(
+            factory.database_provider_ro_at(1),
+            Err(ProviderError::StateForNumberNotFound(1))
+        );
+        let latest = factory.database_provider_ro_at(2).unwrap();
+        assert_eq!(
+            latest.basic_account(account).unwrap().map(|account| account.balance),
+            Some(U256::from(20))
+        );
+    }
 }
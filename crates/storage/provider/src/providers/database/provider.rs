@@ -1557,10 +1557,13 @@ impl<TX: DbTx, Spec: Send + Sync + EthereumHardforks> HeaderProvider
             |static_file, range, predicate| static_file.sealed_headers_while(range, predicate),
             |range, mut predicate| {
                 let mut headers = vec![];
+                // read the hashes alongside the headers, instead of looking up each of them
+                let mut canonical_headers = self.tx.cursor_read::<tables::CanonicalHeaders>()?;
                 for entry in self.tx.cursor_read::<tables::Headers>()?.walk_range(range)? {
                     let (number, header) = entry?;
-                    let hash = self
-                        .block_hash(number)?
+                    let hash = canonical_headers
+                        .seek_exact(number)?
+                        .map(|(_, hash)| hash)
                         .ok_or_else(|| ProviderError::HeaderNotFound(number.into()))?;
                     let sealed = SealedHeader::new(header, hash);
                     if !predicate(&sealed) {
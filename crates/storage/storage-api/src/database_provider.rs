@@ -1,6 +1,8 @@
+use crate::{BlockNumReader, StateProvider};
+use alloy_primitives::BlockNumber;
 use reth_db_api::{database::Database, transaction::DbTx};
 use reth_prune_types::PruneModes;
-use reth_storage_errors::provider::ProviderResult;
+use reth_storage_errors::provider::{ProviderError, ProviderResult};
 
 /// Database provider.
 pub trait DBProvider: Send + Sync + Sized + 'static {
@@ -51,6 +53,46 @@ pub trait DatabaseProviderFactory: Send + Sync {
     /// Create new read-only database provider.
     fn database_provider_ro(&self) -> ProviderResult<Self::Provider>;
 
+    /// Create new read-only database provider over a consistent snapshot of the state as of the
+    /// given block.
+    ///
+    /// All reads through the returned provider observe the database as of the moment it was
+    /// opened, so writes committed afterwards are not visible. The database only keeps the latest
+    /// state, so the snapshot can only be pinned to the block that is currently the tip.
+    ///
+    /// Returns [`ProviderError::HeaderNotFound`] if the block is not yet in the database, and
+    /// [`ProviderError::StateForNumberNotFound`] if the database has already advanced past it.
+    fn database_provider_ro_at(&self, block: BlockNumber) -> ProviderResult<Self::Provider>
+    where
+        Self::Provider: BlockNumReader,
+    {
+        let provider = self.database_provider_ro()?;
+        let last_block_number = provider.last_block_number()?;
+        if last_block_number < block {
+            return Err(ProviderError::HeaderNotFound(block.into()))
+        }
+        if last_block_number > block {
+            return Err(ProviderError::StateForNumberNotFound(block))
+        }
+        Ok(provider)
+    }
+
     /// Create new read-write database provider.
     fn database_provider_rw(&self) -> ProviderResult<Self::ProviderRW>;
 }
+
+/// Database provider factory that can also create state providers.
+///
+/// This allows generic code, e.g. ExExes, to read account and storage state at a given height
+/// using only the database provider factory.
+#[auto_impl::auto_impl(&, Arc)]
+pub trait DatabaseStateProviderFactory: DatabaseProviderFactory {
+    /// State provider type returned by the factory.
+    type StateProvider: StateProvider;
+
+    /// Returns a state provider for the latest state.
+    fn latest_state(&self) -> ProviderResult<Self::StateProvider>;
+
+    /// Returns a state provider for the state at the end of the given historic block.
+    fn history_state_at(&self, block: BlockNumber) -> ProviderResult<Self::StateProvider>;
+}